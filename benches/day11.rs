@@ -26,6 +26,6 @@ mod tests {
     #[divan::bench]
     fn run_part_2(b: Bencher) {
         let input = parse_test_input();
-        b.bench(|| black_box(part_2(&input)));
+        b.bench(|| black_box(part_2(&input, 1_000_000)));
     }
 }
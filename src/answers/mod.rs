@@ -0,0 +1,265 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// Expected answers for the embedded inputs of a day.
+///
+/// Parsed from the `answers.toml` file next to the inputs. Only the subset of TOML needed is
+/// supported: one `[section]` per input, named like the input, with `part_1` and `part_2` keys
+/// holding integers or strings. Each part may only have one answer per input, and the runner
+/// reports sections that do not match an embedded input. An input with answers is only solved for
+/// the parts that have one, so an example can be specific to a part. Inputs without any answer are
+/// solved in full, and their answers reported as unchecked.
+///
+/// ```toml
+/// [example]
+/// part_1 = 142
+///
+/// [input]
+/// part_1 = 54_927
+/// part_2 = "54927"
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct AnswerSheet {
+    entries: Vec<Entry>,
+    /// Names of the sections, in order, including those without answers
+    sections: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    input: String,
+    part: u8,
+    answer: String,
+}
+
+impl AnswerSheet {
+    /// Expected answer for one part of an input
+    #[must_use]
+    pub fn get(&self, input: &str, part: u8) -> Option<&str> {
+        self.entries
+            .iter()
+            .find(|e| e.input == input && e.part == part)
+            .map(|e| e.answer.as_str())
+    }
+
+    /// Returns `true` if the sheet has an answer for any part of the input
+    #[must_use]
+    pub fn has_answers(&self, input: &str) -> bool {
+        self.entries.iter().any(|e| e.input == input)
+    }
+
+    /// Names of the inputs that have a section in the sheet
+    pub fn inputs(&self) -> impl Iterator<Item = &str> {
+        self.sections.iter().map(String::as_str)
+    }
+}
+
+/// Result of comparing an answer to the answer sheet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Pass,
+    Fail {
+        expected: String,
+    },
+    /// No expected answer recorded
+    Unchecked,
+}
+
+impl Verdict {
+    #[must_use]
+    pub fn check(answer: Option<&str>, expected: Option<&str>) -> Self {
+        match expected {
+            None => Self::Unchecked,
+            Some(expected) if Some(expected) == answer => Self::Pass,
+            Some(expected) => Self::Fail {
+                expected: expected.to_string(),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseAnswersError {
+    #[error("Line {0}: Answer outside of an input section")]
+    MissingSection(usize),
+    #[error("Line {0}: Expected 'part_1 = ...' or 'part_2 = ...'")]
+    InvalidKey(usize),
+    #[error("Line {0}: Expected an integer or a quoted string")]
+    InvalidValue(usize),
+    #[error("Line {0}: Unterminated section header")]
+    InvalidSection(usize),
+    #[error("Line {0}: Duplicate answer for part {2} of '{1}'")]
+    DuplicateAnswer(usize, String, u8),
+}
+
+impl FromStr for AnswerSheet {
+    type Err = ParseAnswersError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut sections = Vec::new();
+        let mut section: Option<&str> = None;
+        for (line_ix, line) in text.lines().enumerate() {
+            let line_no = line_ix + 1;
+            let line = match line.split_once('#') {
+                Some((before, _comment)) if !before.contains('"') => before,
+                _ => line,
+            }
+            .trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(header) = line.strip_prefix('[') {
+                let name = header
+                    .strip_suffix(']')
+                    .ok_or(ParseAnswersError::InvalidSection(line_no))?;
+                let name = name.trim();
+                if !sections.iter().any(|section| section == name) {
+                    sections.push(name.to_string());
+                }
+                section = Some(name);
+                continue;
+            }
+            let input = section.ok_or(ParseAnswersError::MissingSection(line_no))?;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(ParseAnswersError::InvalidKey(line_no))?;
+            let part = match key.trim() {
+                "part_1" => 1,
+                "part_2" => 2,
+                _ => return Err(ParseAnswersError::InvalidKey(line_no)),
+            };
+            if entries.iter().any(|e| e.input == input && e.part == part) {
+                return Err(ParseAnswersError::DuplicateAnswer(
+                    line_no,
+                    input.to_string(),
+                    part,
+                ));
+            }
+            let answer =
+                parse_value(value.trim()).ok_or(ParseAnswersError::InvalidValue(line_no))?;
+            entries.push(Entry {
+                input: input.to_string(),
+                part,
+                answer,
+            });
+        }
+        Ok(Self { entries, sections })
+    }
+}
+
+fn parse_value(value: &str) -> Option<String> {
+    if let Some(quoted) = value.strip_prefix('"') {
+        return quoted.strip_suffix('"').map(str::to_string);
+    }
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    if digits.is_empty() || !digits.bytes().all(|ch| ch.is_ascii_digit() || ch == b'_') {
+        return None;
+    }
+    Some(value.replace('_', ""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sections_and_values() {
+        let sheet: AnswerSheet = "\
+# Answers
+[example]
+part_1 = 142 # from the puzzle text
+
+[ input ]
+part_1 = 54_927
+part_2 = \"#54927\"
+
+[negative]
+part_2 = -12
+"
+        .parse()
+        .unwrap();
+        assert_eq!(sheet.get("example", 1), Some("142"));
+        assert_eq!(sheet.get("example", 2), None);
+        assert_eq!(sheet.get("input", 1), Some("54927"));
+        assert_eq!(sheet.get("input", 2), Some("#54927"));
+        assert_eq!(sheet.get("negative", 2), Some("-12"));
+        assert!(sheet.has_answers("negative"));
+        assert!(!sheet.has_answers("example2"));
+        assert_eq!(
+            sheet.inputs().collect::<Vec<_>>(),
+            ["example", "input", "negative"]
+        );
+    }
+
+    #[test]
+    fn sections_without_answers_and_repeated_sections() {
+        let sheet: AnswerSheet = "[input]\npart_1 = 1\n[empty]\n[input]\npart_2 = 2\n"
+            .parse()
+            .unwrap();
+        assert_eq!(sheet.inputs().collect::<Vec<_>>(), ["input", "empty"]);
+        assert_eq!(sheet.get("input", 2), Some("2"));
+        assert!(!sheet.has_answers("empty"));
+    }
+
+    #[test]
+    fn parse_errors_have_line_numbers() {
+        let parse = |text: &str| text.parse::<AnswerSheet>().unwrap_err();
+        assert!(matches!(
+            parse("part_1 = 1"),
+            ParseAnswersError::MissingSection(1)
+        ));
+        assert!(matches!(
+            parse("[input]\npart_3 = 1"),
+            ParseAnswersError::InvalidKey(2)
+        ));
+        assert!(matches!(
+            parse("[input]\n\npart_1"),
+            ParseAnswersError::InvalidKey(3)
+        ));
+        assert!(matches!(
+            parse("[input]\npart_1 = 1.5"),
+            ParseAnswersError::InvalidValue(2)
+        ));
+        assert!(matches!(
+            parse("[input]\npart_1 = \"1"),
+            ParseAnswersError::InvalidValue(2)
+        ));
+        assert!(matches!(
+            parse("[input]\npart_1 = -"),
+            ParseAnswersError::InvalidValue(2)
+        ));
+        assert!(matches!(
+            parse("[input"),
+            ParseAnswersError::InvalidSection(1)
+        ));
+        assert!(matches!(
+            parse("[input]\npart_1 = 1\npart_2 = 2\npart_1 = 1"),
+            ParseAnswersError::DuplicateAnswer(4, input, 1) if input == "input"
+        ));
+        assert!(matches!(
+            parse("[input]\npart_2 = 1\n[example]\n[input]\npart_2 = \"1\""),
+            ParseAnswersError::DuplicateAnswer(5, _, 2)
+        ));
+    }
+
+    #[test]
+    fn verdicts() {
+        assert_eq!(Verdict::check(Some("1"), Some("1")), Verdict::Pass);
+        assert_eq!(
+            Verdict::check(Some("1"), Some("2")),
+            Verdict::Fail {
+                expected: "2".to_string()
+            }
+        );
+        assert_eq!(Verdict::check(Some("1"), None), Verdict::Unchecked);
+        assert_eq!(
+            Verdict::check(None, Some("1")),
+            Verdict::Fail {
+                expected: "1".to_string()
+            }
+        );
+        assert_eq!(Verdict::check(None, None), Verdict::Unchecked);
+    }
+}
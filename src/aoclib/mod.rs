@@ -1,45 +1,77 @@
-#![allow(dead_code)]
-
-use std::fmt::Debug;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{hash_map::Entry, BTreeMap, BinaryHeap, HashMap, HashSet, VecDeque};
+use std::fmt::{Debug, Display, Write};
+use std::hash::Hash;
 use std::num::{IntErrorKind, ParseFloatError, ParseIntError};
-use std::ops::{Add, Div, Index, IndexMut, Mul, Sub};
+use std::ops::{
+    Add, AddAssign, BitAndAssign, BitOrAssign, BitXorAssign, Bound, Div, DivAssign, Index,
+    IndexMut, Mul, MulAssign, Neg, Not, RangeBounds, Sub, SubAssign,
+};
 use std::str::FromStr;
+use std::sync::Arc;
 
 use bstr::ByteSlice;
-use num_traits::{Num, PrimInt};
+use num_traits::{Num, PrimInt, Signed};
 use thiserror::Error;
 
-/// Grid position
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+/// Grid position, or the offset between two positions. Positions are ordered in reading order:
+/// by row, then by column.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Pos {
     row: isize,
     col: isize,
 }
 
 impl Pos {
+    #[must_use]
     pub const fn row(&self) -> isize {
         self.row
     }
 
+    #[must_use]
     pub const fn col(&self) -> isize {
         self.col
     }
 
+    #[must_use]
     pub const fn new(row: isize, col: isize) -> Self {
         Self { row, col }
     }
 
+    #[must_use]
     pub fn manhattan_distance(self, other: Self) -> usize {
         let dr = self.row().abs_diff(other.row());
         let dc = self.col().abs_diff(other.col());
         dr + dc
     }
 
+    #[must_use]
     pub fn eucledian_distance(self, other: Self) -> usize {
         let dr = self.row().abs_diff(other.row());
         let dc = self.col().abs_diff(other.col());
         int_sqrt(dr * dr + dc * dc)
     }
+
+    /// Number of king moves, orthogonal or diagonal, between the positions
+    #[must_use]
+    pub fn chebyshev_distance(self, other: Self) -> usize {
+        let dr = self.row().abs_diff(other.row());
+        let dc = self.col().abs_diff(other.col());
+        dr.max(dc)
+    }
+
+    /// The sign of each coordinate, turning an offset into a single step in its general
+    /// direction
+    #[must_use]
+    pub fn signum(self) -> Self {
+        Self::new(self.row.signum(), self.col.signum())
+    }
+
+    /// Column major order: by column, then by row
+    #[must_use]
+    pub fn cmp_column_major(&self, other: &Self) -> Ordering {
+        (self.col, self.row).cmp(&(other.col, other.row))
+    }
 }
 
 fn int_sqrt<N: PrimInt>(x: N) -> N {
@@ -86,6 +118,58 @@ impl Add<Dir> for Pos {
     }
 }
 
+impl Add for Pos {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.row + rhs.row, self.col + rhs.col)
+    }
+}
+
+impl Sub for Pos {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.row - rhs.row, self.col - rhs.col)
+    }
+}
+
+impl AddAssign for Pos {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl SubAssign for Pos {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Neg for Pos {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.row, -self.col)
+    }
+}
+
+impl Mul<isize> for Pos {
+    type Output = Self;
+
+    fn mul(self, rhs: isize) -> Self::Output {
+        Self::new(self.row * rhs, self.col * rhs)
+    }
+}
+
+impl Add<Dir8> for Pos {
+    type Output = Self;
+
+    fn add(self, rhs: Dir8) -> Self::Output {
+        self + rhs.offset()
+    }
+}
+
 impl Add<MultiDir> for Pos {
     type Output = Self;
 
@@ -114,6 +198,71 @@ pub enum Dir {
 }
 
 impl Dir {
+    /// All directions, clockwise from north. The position of each direction is its
+    /// [`Dir::index`].
+    pub const ALL: [Self; 4] = [Self::N, Self::E, Self::S, Self::W];
+
+    /// Position in [`Dir::ALL`], for indexing arrays by direction
+    #[must_use]
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    /// The direction at `index` in [`Dir::ALL`]
+    #[must_use]
+    pub fn from_index(index: usize) -> Option<Self> {
+        Self::ALL.get(index).copied()
+    }
+
+    /// Parse a direction from any of the alphabets `NESW`, `URDL` or `^>v<`
+    #[must_use]
+    pub fn from_byte(ch: u8) -> Option<Self> {
+        Some(match ch {
+            b'N' | b'U' | b'^' => Self::N,
+            b'E' | b'R' | b'>' => Self::E,
+            b'S' | b'D' | b'v' => Self::S,
+            b'W' | b'L' | b'<' => Self::W,
+            _ => return None,
+        })
+    }
+
+    /// Parse one of `N`, `E`, `S` and `W`
+    #[must_use]
+    pub fn from_nesw(ch: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|dir| dir.to_nesw() == ch)
+    }
+
+    /// Parse one of `U`, `R`, `D` and `L`
+    #[must_use]
+    pub fn from_udlr(ch: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|dir| dir.to_udlr() == ch)
+    }
+
+    /// Parse one of `^`, `>`, `v` and `<`
+    #[must_use]
+    pub fn from_arrow(ch: u8) -> Option<Self> {
+        Self::ALL.into_iter().find(|dir| dir.to_arrow() == ch)
+    }
+
+    /// One of `N`, `E`, `S` and `W`
+    #[must_use]
+    pub const fn to_nesw(self) -> u8 {
+        b"NESW"[self as usize]
+    }
+
+    /// One of `U`, `R`, `D` and `L`
+    #[must_use]
+    pub const fn to_udlr(self) -> u8 {
+        b"URDL"[self as usize]
+    }
+
+    /// One of `^`, `>`, `v` and `<`
+    #[must_use]
+    pub const fn to_arrow(self) -> u8 {
+        b"^>v<"[self as usize]
+    }
+
+    #[must_use]
     pub fn reverse(self) -> Self {
         match self {
             Dir::N => Dir::S,
@@ -123,6 +272,7 @@ impl Dir {
         }
     }
 
+    #[must_use]
     pub fn turn_cw(self) -> Self {
         match self {
             Dir::N => Dir::E,
@@ -132,9 +282,141 @@ impl Dir {
         }
     }
 
+    #[must_use]
     pub fn turn_ccw(self) -> Self {
         self.turn_cw().reverse()
     }
+
+    /// The direction after making `turn`
+    #[must_use]
+    pub fn turn(self, turn: Turn) -> Self {
+        match turn {
+            Turn::Left => self.turn_ccw(),
+            Turn::Right => self.turn_cw(),
+            Turn::Straight => self,
+            Turn::Back => self.reverse(),
+        }
+    }
+
+    /// The turn from this direction to `other`
+    #[must_use]
+    pub fn turn_to(self, other: Self) -> Turn {
+        match (other.index() + 4 - self.index()) % 4 {
+            0 => Turn::Straight,
+            1 => Turn::Right,
+            2 => Turn::Back,
+            _ => Turn::Left,
+        }
+    }
+}
+
+impl TryFrom<u8> for Dir {
+    type Error = CommonParseError;
+
+    fn try_from(ch: u8) -> Result<Self, Self::Error> {
+        Self::from_byte(ch).ok_or(CommonParseError::InvalidDirection(ch as char))
+    }
+}
+
+/// A turn relative to the current direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Turn {
+    Left,
+    Right,
+    Straight,
+    Back,
+}
+
+impl Turn {
+    /// Parse `L` or `R`
+    #[must_use]
+    pub fn from_lr(ch: u8) -> Option<Self> {
+        match ch {
+            b'L' => Some(Self::Left),
+            b'R' => Some(Self::Right),
+            _ => None,
+        }
+    }
+}
+
+/// One of the eight directions of a king move, clockwise from north
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Dir8 {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Dir8 {
+    /// All directions, clockwise from north
+    pub const ALL: [Self; 8] = [
+        Self::N,
+        Self::NE,
+        Self::E,
+        Self::SE,
+        Self::S,
+        Self::SW,
+        Self::W,
+        Self::NW,
+    ];
+
+    /// Offset of a single step in this direction
+    #[must_use]
+    pub const fn offset(self) -> Pos {
+        match self {
+            Self::N => Pos::new(-1, 0),
+            Self::NE => Pos::new(-1, 1),
+            Self::E => Pos::new(0, 1),
+            Self::SE => Pos::new(1, 1),
+            Self::S => Pos::new(1, 0),
+            Self::SW => Pos::new(1, -1),
+            Self::W => Pos::new(0, -1),
+            Self::NW => Pos::new(-1, -1),
+        }
+    }
+
+    #[must_use]
+    pub const fn is_diagonal(self) -> bool {
+        matches!(self, Self::NE | Self::SE | Self::SW | Self::NW)
+    }
+
+    #[must_use]
+    pub fn reverse(self) -> Self {
+        self.rotate(4)
+    }
+
+    /// Turn 45 degrees clockwise
+    #[must_use]
+    pub fn turn_cw(self) -> Self {
+        self.rotate(1)
+    }
+
+    /// Turn 45 degrees counterclockwise
+    #[must_use]
+    pub fn turn_ccw(self) -> Self {
+        self.rotate(7)
+    }
+
+    /// Turn `steps` times 45 degrees clockwise
+    fn rotate(self, steps: usize) -> Self {
+        Self::ALL[(self as usize + steps) % 8]
+    }
+}
+
+impl From<Dir> for Dir8 {
+    fn from(dir: Dir) -> Self {
+        match dir {
+            Dir::N => Self::N,
+            Dir::E => Self::E,
+            Dir::S => Self::S,
+            Dir::W => Self::W,
+        }
+    }
 }
 
 impl Mul<usize> for Dir {
@@ -152,10 +434,12 @@ pub struct MultiDir {
 }
 
 impl MultiDir {
+    #[must_use]
     pub fn dir(&self) -> Dir {
         self.dir
     }
 
+    #[must_use]
     pub fn count(&self) -> usize {
         self.count
     }
@@ -172,12 +456,14 @@ impl<T> Grid<T>
 where
     T: Copy + Default,
 {
+    #[must_use]
     pub fn new(width: usize, height: usize) -> Self {
         Self::from_vec(width, height, vec![Default::default(); width * height])
     }
 }
 
 impl<T> Grid<T> {
+    #[must_use]
     pub fn from_vec(width: usize, height: usize, values: Vec<T>) -> Self {
         assert_eq!(values.len(), width * height);
         Self {
@@ -187,10 +473,12 @@ impl<T> Grid<T> {
         }
     }
 
+    #[must_use]
     pub const fn width(&self) -> usize {
         self.width
     }
 
+    #[must_use]
     pub const fn height(&self) -> usize {
         self.height
     }
@@ -206,6 +494,7 @@ impl<T> Grid<T> {
         }
     }
 
+    #[must_use]
     pub fn get_row(&self, row: isize) -> Option<&[T]> {
         let row_usize = usize::try_from(row).ok()?;
         if (0..self.height).contains(&row_usize) {
@@ -225,6 +514,7 @@ impl<T> Grid<T> {
     }
 
     #[inline]
+    #[must_use]
     pub fn is_inside(&self, pos: Pos) -> bool {
         let Ok(height) = isize::try_from(self.height) else {
             return false;
@@ -246,6 +536,61 @@ impl<T> Grid<T> {
             None
         }
     }
+
+    #[inline]
+    fn to_pos(&self, ix: usize) -> Pos {
+        Pos::new(
+            isize::try_from(ix / self.width).unwrap(),
+            isize::try_from(ix % self.width).unwrap(),
+        )
+    }
+
+    /// All cells with their positions, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.values
+            .iter()
+            .enumerate()
+            .map(|(ix, value)| (self.to_pos(ix), value))
+    }
+
+    /// All cells with their positions, row by row
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Pos, &mut T)> {
+        let width = self.width;
+        self.values.iter_mut().enumerate().map(move |(ix, value)| {
+            let pos = Pos::new(
+                isize::try_from(ix / width).unwrap(),
+                isize::try_from(ix % width).unwrap(),
+            );
+            (pos, value)
+        })
+    }
+
+    /// The rows from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        // A grid without columns has no values, and no rows
+        self.values.chunks_exact(self.width.max(1))
+    }
+
+    /// The columns from left to right, each iterating from top to bottom
+    pub fn columns(&self) -> impl Iterator<Item = impl Iterator<Item = &T>> {
+        (0..self.width).map(|col| self.values[col..].iter().step_by(self.width))
+    }
+
+    /// The orthogonal neighbours of `pos` that are inside the grid, in the order N, E, S, W
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Dir::ALL
+            .into_iter()
+            .map(move |dir| pos + dir)
+            .filter(|&next| self.is_inside(next))
+    }
+
+    /// The orthogonal and diagonal neighbours of `pos` that are inside the grid, clockwise from N
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
+        Dir8::ALL
+            .into_iter()
+            .map(move |dir| pos + dir)
+            .filter(|&next| self.is_inside(next))
+    }
 }
 
 impl<T> Grid<T>
@@ -253,22 +598,17 @@ where
     T: Copy,
 {
     pub fn position(&self, mut check: impl FnMut(T) -> bool) -> Option<Pos> {
-        for r in 0..self.height {
-            for (c, &value) in self
-                .get_row(isize::try_from(r).unwrap())
-                .unwrap()
-                .iter()
-                .enumerate()
-            {
-                if check(value) {
-                    return Some(Pos::new(
-                        isize::try_from(r).unwrap(),
-                        isize::try_from(c).unwrap(),
-                    ));
-                }
-            }
-        }
-        None
+        self.iter()
+            .find_map(|(pos, &value)| check(value).then_some(pos))
+    }
+
+    /// Positions of all cells matching `check`, row by row
+    pub fn find_all<'a>(
+        &'a self,
+        mut check: impl FnMut(T) -> bool + 'a,
+    ) -> impl Iterator<Item = Pos> + 'a {
+        self.iter()
+            .filter_map(move |(pos, &value)| check(value).then_some(pos))
     }
 
     pub fn count_if(&self, mut check: impl FnMut(T) -> bool) -> usize {
@@ -287,6 +627,7 @@ where
         }
     }
 
+    #[must_use]
     pub fn get(&self, pos: Pos) -> Option<T> {
         Some(self.values[self.to_index(pos)?])
     }
@@ -297,347 +638,2965 @@ where
             *x = f(*x);
         }
     }
-}
 
-impl<T> Index<Pos> for Grid<T> {
-    type Output = T;
-
-    fn index(&self, index: Pos) -> &Self::Output {
-        let ix = self.to_index(index).unwrap();
-        &self.values[ix]
+    /// Build a `width` by `height` grid, taking the value at each `(row, col)` from the cell at
+    /// `source(row, col)` of this grid
+    fn remap(
+        &self,
+        width: usize,
+        height: usize,
+        source: impl Fn(usize, usize) -> (usize, usize),
+    ) -> Self {
+        let mut values = Vec::with_capacity(width * height);
+        for row in 0..height {
+            for col in 0..width {
+                let (src_row, src_col) = source(row, col);
+                values.push(self.values[src_row * self.width + src_col]);
+            }
+        }
+        Self::from_vec(width, height, values)
     }
-}
 
-impl<T> IndexMut<Pos> for Grid<T> {
-    fn index_mut(&mut self, index: Pos) -> &mut Self::Output {
-        let ix = self.to_index(index).unwrap();
-        &mut self.values[ix]
+    /// Swap rows and columns, mirroring the grid along the main diagonal
+    #[must_use]
+    pub fn transpose(&self) -> Self {
+        self.remap(self.height, self.width, |row, col| (col, row))
     }
-}
 
-impl<T> Debug for Grid<T>
-where
-    T: Debug,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let stride = self.width;
-        writeln!(f, "[")?;
-        for r in 0..self.height {
-            write!(f, "  ")?;
-            for cell in &self.values[r * stride..(r + 1) * stride] {
-                write!(f, "{cell:?}")?;
-            }
-            writeln!(f)?;
-        }
-        write!(f, "]")
+    /// Rotate the grid 90 degrees clockwise
+    #[must_use]
+    pub fn rotate_cw(&self) -> Self {
+        let height = self.height;
+        self.remap(self.height, self.width, |row, col| (height - 1 - col, row))
     }
-}
 
-impl<T> FromStr for Grid<T>
-where
-    T: TryFrom<u8> + Default + Copy,
-    CommonParseError: Into<<T as TryFrom<u8>>::Error>,
-    // reverse of <T as TryFrom<u8>>::Error: From<CommonParseError> -- But the type checker didn't like this
-{
-    type Err = <T as TryFrom<u8>>::Error;
+    /// Rotate the grid 90 degrees counter-clockwise
+    #[must_use]
+    pub fn rotate_ccw(&self) -> Self {
+        let width = self.width;
+        self.remap(self.height, self.width, |row, col| (col, width - 1 - row))
+    }
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.as_bytes().lines().enumerate();
-        let first_line = lines.next().ok_or(CommonParseError::EmptyInput.into())?.1;
-        let width = first_line.len();
-        let mut height = 1;
-        let mut values: Vec<T> = Vec::with_capacity(width * width);
-        let mut row = [(); 141].map(|()| T::default());
-        for x in 0..width {
-            row[x] = first_line[x].try_into()?;
-        }
-        values.extend(&row[..width]);
-        for (r, line) in lines {
-            height = r + 1;
-            for x in 0..width {
-                row[x] = line[x].try_into()?;
-            }
-            values.extend(&row[..width]);
-        }
-        Ok(Self {
-            width,
-            height,
-            values,
+    /// Rotate the grid 180 degrees
+    #[must_use]
+    pub fn rotate_180(&self) -> Self {
+        let (width, height) = (self.width, self.height);
+        self.remap(width, height, |row, col| {
+            (height - 1 - row, width - 1 - col)
         })
     }
-}
-
-pub struct RepeatingGrid<'a, T>(&'a Grid<T>);
 
-impl<'a, T> RepeatingGrid<'a, T> {
-    pub fn new(grid: &'a Grid<T>) -> Self {
-        Self(grid)
+    /// Mirror the grid left to right
+    #[must_use]
+    pub fn flip_horizontal(&self) -> Self {
+        let width = self.width;
+        self.remap(width, self.height, |row, col| (row, width - 1 - col))
     }
-}
-
-impl<'a, T> Index<Pos> for RepeatingGrid<'a, T> {
-    type Output = T;
 
-    fn index(&self, index: Pos) -> &Self::Output {
-        let pos = Pos::new(
-            index
-                .row()
-                .rem_euclid(isize::try_from(self.0.height).unwrap()),
-            index
-                .col()
-                .rem_euclid(isize::try_from(self.0.width).unwrap()),
-        );
-        &self.0[pos]
+    /// Mirror the grid top to bottom
+    #[must_use]
+    pub fn flip_vertical(&self) -> Self {
+        let height = self.height;
+        self.remap(self.width, height, |row, col| (height - 1 - row, col))
     }
 }
 
-pub fn gcd<T>(mut a: T, mut b: T) -> T
-where
-    T: Copy + Num,
-{
-    let zero = T::zero();
-    while a != zero {
-        let r = b % a;
-        b = a;
-        a = r;
+impl<T> Grid<T> {
+    /// Borrow the whole grid as a view
+    #[must_use]
+    pub fn as_view(&self) -> GridView<'_, T> {
+        GridView {
+            grid: self,
+            origin: Pos::new(0, 0),
+            width: self.width,
+            height: self.height,
+        }
     }
-    b
-}
-
-pub fn lcm<T>(a: T, b: T) -> T
-where
-    T: Copy + Num,
-{
-    a * b / gcd(a, b)
-}
 
-#[derive(Debug, Error)]
-pub enum CommonParseError {
-    #[error("Input is empty")]
-    EmptyInput,
-    #[error("Expected char: {0:?}")]
-    ExpectedChar(char),
-    #[error("Invalid integer: {0:?}")]
-    InvalidInteger(#[from] ParseIntError),
-    #[error("Invalid integer: {0:?}")]
-    InvalidInteger2(#[from] ParseIntError2),
-    #[error("Invalid integer: {0:?}")]
-    InvalidFloat(#[from] ParseFloatError),
-}
+    /// Borrow the `width` by `height` rectangle with its top left corner at `origin`. Returns
+    /// `None` if the rectangle does not fit inside the grid.
+    #[must_use]
+    pub fn view(&self, origin: Pos, width: usize, height: usize) -> Option<GridView<'_, T>> {
+        let row = usize::try_from(origin.row).ok()?;
+        let col = usize::try_from(origin.col).ok()?;
+        (row + height <= self.height && col + width <= self.width).then_some(GridView {
+            grid: self,
+            origin,
+            width,
+            height,
+        })
+    }
 
-#[derive(Copy, Clone)]
-pub struct Coordinate<T> {
-    pub x: T,
-    pub y: T,
-    pub z: T,
+    /// All `width` by `height` views that fit inside the grid, row by row
+    pub fn windows(&self, width: usize, height: usize) -> impl Iterator<Item = GridView<'_, T>> {
+        let rows = (self.height + 1).saturating_sub(height);
+        let cols = (self.width + 1).saturating_sub(width);
+        (0..rows).flat_map(move |row| {
+            (0..cols).map(move |col| GridView {
+                grid: self,
+                origin: Pos::new(isize::try_from(row).unwrap(), isize::try_from(col).unwrap()),
+                width,
+                height,
+            })
+        })
+    }
 }
 
-impl<T> Coordinate<T> {
-    pub fn new(x: T, y: T, z: T) -> Self {
-        Self { x, y, z }
-    }
+/// Shortest distances from the start of a search over a [`Grid`], with the previous step of each
+/// reached position, to reconstruct the paths.
+#[derive(Clone)]
+pub struct DistanceMap {
+    distances: Grid<Option<usize>>,
+    previous: Grid<Option<Pos>>,
 }
 
-impl<T> Coordinate<T>
-where
-    T: Ord,
-{
-    pub fn min_fields(self, other: Self) -> Self {
+impl DistanceMap {
+    fn new(width: usize, height: usize) -> Self {
         Self {
-            x: self.x.min(other.x),
-            y: self.y.min(other.y),
-            z: self.z.min(other.z),
+            distances: Grid::new(width, height),
+            previous: Grid::new(width, height),
         }
     }
-    pub fn max_fields(self, other: Self) -> Self {
-        Self {
-            x: self.x.max(other.x),
-            y: self.y.max(other.y),
-            z: self.z.max(other.z),
-        }
-    }
-}
 
-impl<T> Debug for Coordinate<T>
-where
-    T: Debug,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.debug_tuple("")
-            .field(&self.x)
-            .field(&self.y)
-            .field(&self.z)
-            .finish()
+    /// Distance to `pos`, or `None` if it was not reached
+    #[must_use]
+    pub fn distance(&self, pos: Pos) -> Option<usize> {
+        self.distances.get(pos).flatten()
     }
-}
-
-impl<'a, T> TryFrom<&'a [u8]> for Coordinate<T>
-where
-    T: PrimInt,
-{
-    type Error = CommonParseError;
 
-    fn try_from(s: &'a [u8]) -> Result<Self, Self::Error> {
-        let comma1 = s
-            .find_byte(b',')
-            .ok_or(CommonParseError::ExpectedChar(','))?;
-        let comma2 = s[comma1 + 1..]
-            .find_byte(b',')
-            .ok_or(CommonParseError::ExpectedChar(','))?;
-        let x = parse_int(&s[..comma1])?;
-        let y = parse_int(&s[comma1 + 1..comma1 + 1 + comma2])?;
-        let z = parse_int(&s[comma1 + comma2 + 2..])?;
-        Ok(Self::new(x, y, z))
+    /// Distance to every position of the grid, `None` where it was not reached
+    #[must_use]
+    pub fn distances(&self) -> &Grid<Option<usize>> {
+        &self.distances
     }
-}
 
-impl<T> Add for Coordinate<T>
-where
-    T: Add<T, Output = T>,
-{
-    type Output = Self;
+    #[must_use]
+    pub fn into_distances(self) -> Grid<Option<usize>> {
+        self.distances
+    }
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x + rhs.x,
-            y: self.y + rhs.y,
-            z: self.z + rhs.z,
+    /// A shortest path from a start to `target`, both included, or `None` if `target` was not
+    /// reached
+    #[must_use]
+    pub fn path_to(&self, target: Pos) -> Option<Vec<Pos>> {
+        self.distance(target)?;
+        let mut path = vec![target];
+        let mut pos = target;
+        while let Some(prev) = self.previous[pos] {
+            path.push(prev);
+            pos = prev;
         }
+        path.reverse();
+        Some(path)
     }
 }
 
-impl<T> Sub for Coordinate<T>
-where
-    T: Sub<T, Output = T>,
-{
-    type Output = Self;
+impl<T> Grid<T> {
+    /// Breadth first search from all of `starts`, moving orthogonally from `from` to `to` where
+    /// `passable(from, to)` allows it. Each step has distance 1.
+    pub fn bfs(
+        &self,
+        starts: impl IntoIterator<Item = Pos>,
+        mut passable: impl FnMut(Pos, Pos) -> bool,
+    ) -> DistanceMap {
+        let mut map = DistanceMap::new(self.width, self.height);
+        let mut queue = VecDeque::new();
+        for start in starts {
+            if self.is_inside(start) && map.distances[start].is_none() {
+                map.distances[start] = Some(0);
+                queue.push_back(start);
+            }
+        }
+        while let Some(pos) = queue.pop_front() {
+            let dist = map.distances[pos].unwrap();
+            for next in self.neighbours4(pos) {
+                if map.distances[next].is_none() && passable(pos, next) {
+                    map.distances[next] = Some(dist + 1);
+                    map.previous[next] = Some(pos);
+                    queue.push_back(next);
+                }
+            }
+        }
+        map
+    }
+
+    /// All positions reachable from `start` with orthogonal moves allowed by `passable(from, to)`,
+    /// `start` first, in order of distance
+    pub fn flood_fill(&self, start: Pos, mut passable: impl FnMut(Pos, Pos) -> bool) -> Vec<Pos> {
+        if !self.is_inside(start) {
+            return Vec::new();
+        }
+        let mut seen = Grid::<bool>::new(self.width, self.height);
+        seen[start] = true;
+        let mut filled = vec![start];
+        let mut ix = 0;
+        while let Some(&pos) = filled.get(ix) {
+            for next in self.neighbours4(pos) {
+                if !seen[next] && passable(pos, next) {
+                    seen[next] = true;
+                    filled.push(next);
+                }
+            }
+            ix += 1;
+        }
+        filled
+    }
+
+    /// Label the connected components of the positions where `passable(pos, value)`, with
+    /// orthogonal neighbours connected if `connected(a, b)`. Returns the label of each passable
+    /// position, numbered from 0 in the order their first position appears row by row, and the
+    /// number of components.
+    pub fn components(
+        &self,
+        mut passable: impl FnMut(Pos, &T) -> bool,
+        mut connected: impl FnMut(Pos, Pos) -> bool,
+    ) -> (Grid<Option<usize>>, usize) {
+        let is_passable = self
+            .iter()
+            .map(|(pos, value)| passable(pos, value))
+            .collect();
+        let is_passable = Grid::from_vec(self.width, self.height, is_passable);
+        let mut labels = Grid::<Option<usize>>::new(self.width, self.height);
+        let mut count = 0;
+        let mut stack = Vec::new();
+        for (start, &start_passable) in is_passable.iter() {
+            if !start_passable || labels[start].is_some() {
+                continue;
+            }
+            labels[start] = Some(count);
+            stack.push(start);
+            while let Some(pos) = stack.pop() {
+                for next in self.neighbours4(pos) {
+                    if labels[next].is_none() && is_passable[next] && connected(pos, next) {
+                        labels[next] = Some(count);
+                        stack.push(next);
+                    }
+                }
+            }
+            count += 1;
+        }
+        (labels, count)
+    }
+
+    /// Shortest distances from all of `starts`, moving orthogonally from `from` to `to` for
+    /// `cost(from, to)`, or not at all where it is `None`
+    pub fn dijkstra(
+        &self,
+        starts: impl IntoIterator<Item = Pos>,
+        cost: impl FnMut(Pos, Pos) -> Option<usize>,
+    ) -> DistanceMap {
+        self.shortest_paths(starts, None, cost, |_| 0)
+    }
+
+    /// Shortest path search from `start` towards `goal`, moving orthogonally from `from` to `to`
+    /// for `cost(from, to)`, or not at all where it is `None`. `heuristic(pos)` must never be more
+    /// than the actual distance from `pos` to `goal`.
+    ///
+    /// The search stops once `goal` is reached, so only the distance to `goal` and the positions
+    /// on its path are known to be the shortest.
+    pub fn astar(
+        &self,
+        start: Pos,
+        goal: Pos,
+        cost: impl FnMut(Pos, Pos) -> Option<usize>,
+        heuristic: impl FnMut(Pos) -> usize,
+    ) -> DistanceMap {
+        self.shortest_paths([start], Some(goal), cost, heuristic)
+    }
+
+    fn shortest_paths(
+        &self,
+        starts: impl IntoIterator<Item = Pos>,
+        goal: Option<Pos>,
+        mut cost: impl FnMut(Pos, Pos) -> Option<usize>,
+        mut heuristic: impl FnMut(Pos) -> usize,
+    ) -> DistanceMap {
+        let mut map = DistanceMap::new(self.width, self.height);
+        // Entries are (estimated total, distance, position)
+        let mut queue = BinaryHeap::new();
+        for start in starts {
+            if self.is_inside(start) {
+                map.distances[start] = Some(0);
+                queue.push(Reverse((heuristic(start), 0, start)));
+            }
+        }
+        while let Some(Reverse((_, dist, pos))) = queue.pop() {
+            if map.distances[pos] != Some(dist) {
+                // Already reached with a shorter distance
+                continue;
+            }
+            if Some(pos) == goal {
+                break;
+            }
+            for next in self.neighbours4(pos) {
+                let Some(step) = cost(pos, next) else {
+                    continue;
+                };
+                let next_dist = dist + step;
+                if map.distances[next].is_none_or(|old| next_dist < old) {
+                    map.distances[next] = Some(next_dist);
+                    map.previous[next] = Some(pos);
+                    queue.push(Reverse((next_dist + heuristic(next), next_dist, next)));
+                }
+            }
+        }
+        map
+    }
+}
+
+/// Borrowed rectangular part of a [`Grid`]. Positions are relative to the top left corner of the
+/// view.
+pub struct GridView<'a, T> {
+    grid: &'a Grid<T>,
+    origin: Pos,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Clone for GridView<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for GridView<'_, T> {}
+
+impl<'a, T> GridView<'a, T> {
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Position of the top left corner in the underlying grid
+    #[must_use]
+    pub const fn origin(&self) -> Pos {
+        self.origin
+    }
+
+    #[must_use]
+    pub fn is_inside(&self, pos: Pos) -> bool {
+        usize::try_from(pos.row).is_ok_and(|row| row < self.height)
+            && usize::try_from(pos.col).is_ok_and(|col| col < self.width)
+    }
+
+    #[must_use]
+    pub fn get(&self, pos: Pos) -> Option<&'a T> {
+        if self.is_inside(pos) {
+            let grid: &'a Grid<T> = self.grid;
+            Some(&grid[Pos::new(self.origin.row + pos.row, self.origin.col + pos.col)])
+        } else {
+            None
+        }
+    }
+
+    /// The rows from top to bottom
+    pub fn rows(&self) -> impl Iterator<Item = &'a [T]> {
+        let grid: &'a Grid<T> = self.grid;
+        let col = usize::try_from(self.origin.col).unwrap();
+        let width = self.width;
+        (self.origin.row..self.origin.row + isize::try_from(self.height).unwrap())
+            .map(move |row| &grid.get_row(row).unwrap()[col..col + width])
+    }
+
+    /// All cells with their positions relative to the view, row by row
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &'a T)> {
+        self.rows().enumerate().flat_map(|(row, values)| {
+            values.iter().enumerate().map(move |(col, value)| {
+                let pos = Pos::new(isize::try_from(row).unwrap(), isize::try_from(col).unwrap());
+                (pos, value)
+            })
+        })
+    }
+}
+
+impl<T> GridView<'_, T>
+where
+    T: Copy,
+{
+    /// Copy the cells of the view into a new grid
+    #[must_use]
+    pub fn to_grid(self) -> Grid<T> {
+        Grid::from_vec(
+            self.width,
+            self.height,
+            self.rows().flatten().copied().collect(),
+        )
+    }
+}
+
+impl<T> Index<Pos> for GridView<'_, T> {
+    type Output = T;
+
+    fn index(&self, index: Pos) -> &Self::Output {
+        self.get(index).unwrap()
+    }
+}
+
+impl<T> Index<Pos> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, index: Pos) -> &Self::Output {
+        let ix = self.to_index(index).unwrap();
+        &self.values[ix]
+    }
+}
+
+impl<T> IndexMut<Pos> for Grid<T> {
+    fn index_mut(&mut self, index: Pos) -> &mut Self::Output {
+        let ix = self.to_index(index).unwrap();
+        &mut self.values[ix]
+    }
+}
+
+impl<T> Debug for Grid<T>
+where
+    T: Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let stride = self.width;
+        writeln!(f, "[")?;
+        for r in 0..self.height {
+            write!(f, "  ")?;
+            for cell in &self.values[r * stride..(r + 1) * stride] {
+                write!(f, "{cell:?}")?;
+            }
+            writeln!(f)?;
+        }
+        write!(f, "]")
+    }
+}
+
+/// Inverse of the `TryFrom<u8>` conversion used to parse the cells of a [`Grid`]
+pub trait CellByte: Copy {
+    fn to_byte(self) -> u8;
+}
+
+impl CellByte for u8 {
+    fn to_byte(self) -> u8 {
+        self
+    }
+}
+
+/// Renders the grid in the puzzle format, with a newline after each row
+impl<T> Display for Grid<T>
+where
+    T: CellByte,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.overlay().fmt(f)
+    }
+}
+
+impl<T> Grid<T> {
+    /// Render the grid with some positions highlighted, see [`Overlay::highlight`]
+    #[must_use]
+    pub fn overlay(&self) -> Overlay<'_, T> {
+        Overlay {
+            grid: self,
+            marks: HashMap::new(),
+        }
+    }
+}
+
+/// A grid rendered in the puzzle format, with some positions replaced by marker characters
+pub struct Overlay<'a, T> {
+    grid: &'a Grid<T>,
+    marks: HashMap<Pos, char>,
+}
+
+impl<T> Overlay<'_, T> {
+    /// Show `mark` instead of the cell at each of `positions`. Later highlights replace earlier
+    /// ones, and positions outside of the grid are ignored.
+    #[must_use]
+    pub fn highlight(mut self, positions: impl IntoIterator<Item = Pos>, mark: char) -> Self {
+        self.marks
+            .extend(positions.into_iter().map(|pos| (pos, mark)));
+        self
+    }
+}
+
+impl<T> Display for Overlay<'_, T>
+where
+    T: CellByte,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (pos, &cell) in self.grid.iter() {
+            match self.marks.get(&pos) {
+                Some(&mark) => f.write_char(mark)?,
+                None => f.write_char(char::from(cell.to_byte()))?,
+            }
+            if usize::try_from(pos.col).unwrap() + 1 == self.grid.width {
+                f.write_char('\n')?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T> FromStr for Grid<T>
+where
+    T: TryFrom<u8> + Default + Copy,
+    <T as TryFrom<u8>>::Error: std::error::Error + Send + Sync + 'static,
+    CommonParseError: Into<<T as TryFrom<u8>>::Error>,
+    // reverse of <T as TryFrom<u8>>::Error: From<CommonParseError> -- But the type checker didn't like this
+{
+    type Err = <T as TryFrom<u8>>::Error;
+
+    /// Parse a grid of one cell per byte. Lines may end with LF or CRLF, and all lines must be
+    /// equally long.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lines = grid_lines(s.as_bytes()).map_err(Into::into)?;
+        let width = lines[0].1.len();
+        let height = lines.len();
+        let mut values: Vec<T> = Vec::with_capacity(width * height);
+        for (offset, line) in lines {
+            for (col, &cell) in line.iter().enumerate() {
+                values.push(
+                    cell.try_into()
+                        .map_err(|err| CommonParseError::invalid_cell(err, offset + col))?,
+                );
+            }
+        }
+        Ok(Self {
+            width,
+            height,
+            values,
+        })
+    }
+}
+
+/// A [`Grid`] repeated infinitely in all directions. The copy with its top left cell at `(0, 0)`
+/// is tile `(0, 0)`, the copy to the east of it tile `(0, 1)`.
+pub struct RepeatingGrid<'a, T>(&'a Grid<T>);
+
+impl<'a, T> RepeatingGrid<'a, T> {
+    #[must_use]
+    pub fn new(grid: &'a Grid<T>) -> Self {
+        Self(grid)
+    }
+
+    /// The repeated grid
+    #[must_use]
+    pub fn grid(&self) -> &'a Grid<T> {
+        self.0
+    }
+
+    /// Width of a single tile
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.0.width
+    }
+
+    /// Height of a single tile
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.0.height
+    }
+
+    /// The tile containing `pos`, and the position within that tile
+    #[must_use]
+    pub fn split(&self, pos: Pos) -> (Pos, Pos) {
+        let height = isize::try_from(self.0.height).unwrap();
+        let width = isize::try_from(self.0.width).unwrap();
+        (
+            Pos::new(pos.row.div_euclid(height), pos.col.div_euclid(width)),
+            Pos::new(pos.row.rem_euclid(height), pos.col.rem_euclid(width)),
+        )
+    }
+
+    /// The position of `local` within `tile`, the inverse of [`RepeatingGrid::split`]
+    #[must_use]
+    pub fn join(&self, tile: Pos, local: Pos) -> Pos {
+        let height = isize::try_from(self.0.height).unwrap();
+        let width = isize::try_from(self.0.width).unwrap();
+        Pos::new(tile.row * height + local.row, tile.col * width + local.col)
+    }
+
+    /// The tile containing `pos`
+    #[must_use]
+    pub fn tile(&self, pos: Pos) -> Pos {
+        self.split(pos).0
+    }
+
+    /// Number of distinct tiles containing any of `positions`
+    pub fn count_tiles(&self, positions: impl IntoIterator<Item = Pos>) -> usize {
+        positions
+            .into_iter()
+            .map(|pos| self.tile(pos))
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// The orthogonal neighbours of `pos`, in the order N, E, S, W
+    #[allow(clippy::unused_self)]
+    pub fn neighbours4(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        Dir::ALL.into_iter().map(move |dir| pos + dir)
+    }
+
+    /// The orthogonal and diagonal neighbours of `pos`, clockwise from N
+    #[allow(clippy::unused_self)]
+    pub fn neighbours8(&self, pos: Pos) -> impl Iterator<Item = Pos> {
+        Dir8::ALL.into_iter().map(move |dir| pos + dir)
+    }
+}
+
+impl<'a, T> Index<Pos> for RepeatingGrid<'a, T> {
+    type Output = T;
+
+    fn index(&self, index: Pos) -> &Self::Output {
+        &self.0[self.split(index).1]
+    }
+}
+
+/// Unbounded grid of the occupied cells only, accepting any position, including negative ones
+#[derive(Debug, Clone)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Pos, T>,
+    /// Number of occupied cells in each row with any, to find the bounds after removals
+    row_counts: BTreeMap<isize, usize>,
+    /// Number of occupied cells in each column with any
+    col_counts: BTreeMap<isize, usize>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        Self {
+            cells: HashMap::new(),
+            row_counts: BTreeMap::new(),
+            col_counts: BTreeMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    #[must_use]
+    pub fn contains(&self, pos: Pos) -> bool {
+        self.cells.contains_key(&pos)
+    }
+
+    #[must_use]
+    pub fn get(&self, pos: Pos) -> Option<&T> {
+        self.cells.get(&pos)
+    }
+
+    pub fn get_mut(&mut self, pos: Pos) -> Option<&mut T> {
+        self.cells.get_mut(&pos)
+    }
+
+    /// Occupy `pos` with `value`, returning the previous value
+    pub fn insert(&mut self, pos: Pos, value: T) -> Option<T> {
+        let previous = self.cells.insert(pos, value);
+        if previous.is_none() {
+            *self.row_counts.entry(pos.row).or_default() += 1;
+            *self.col_counts.entry(pos.col).or_default() += 1;
+        }
+        previous
+    }
+
+    /// Empty `pos`, returning its value
+    pub fn remove(&mut self, pos: Pos) -> Option<T> {
+        let value = self.cells.remove(&pos)?;
+        Self::decrement(&mut self.row_counts, pos.row);
+        Self::decrement(&mut self.col_counts, pos.col);
+        Some(value)
+    }
+
+    /// Top left and bottom right corners of the smallest rectangle containing all occupied
+    /// cells, or `None` if the grid is empty
+    #[must_use]
+    pub fn bounds(&self) -> Option<(Pos, Pos)> {
+        let (&min_row, _) = self.row_counts.first_key_value()?;
+        let (&max_row, _) = self.row_counts.last_key_value()?;
+        let (&min_col, _) = self.col_counts.first_key_value()?;
+        let (&max_col, _) = self.col_counts.last_key_value()?;
+        Some((Pos::new(min_row, min_col), Pos::new(max_row, max_col)))
+    }
+
+    /// All occupied cells with their positions, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (Pos, &T)> {
+        self.cells.iter().map(|(&pos, value)| (pos, value))
+    }
+
+    /// All occupied cells with their positions, in no particular order
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Pos, &mut T)> {
+        self.cells.iter_mut().map(|(&pos, value)| (pos, value))
+    }
+
+    fn decrement(counts: &mut BTreeMap<isize, usize>, key: isize) {
+        if let Some(count) = counts.get_mut(&key) {
+            *count -= 1;
+            if *count == 0 {
+                counts.remove(&key);
+            }
+        }
+    }
+}
+
+impl<T> SparseGrid<T>
+where
+    T: Copy,
+{
+    /// Occupy the cells of `grid` for which `occupied` is true, with the top left cell of `grid`
+    /// at `origin`
+    pub fn from_grid(grid: &Grid<T>, origin: Pos, mut occupied: impl FnMut(T) -> bool) -> Self {
+        grid.iter()
+            .filter(|&(_, &value)| occupied(value))
+            .map(|(pos, &value)| (Pos::new(origin.row + pos.row, origin.col + pos.col), value))
+            .collect()
+    }
+
+    /// The bounding box as a dense grid, with `empty` in the cells that are not occupied, and the
+    /// position of its top left cell. Returns `None` if the grid is empty.
+    #[allow(clippy::cast_sign_loss)]
+    pub fn to_grid(&self, empty: T) -> Option<(Grid<T>, Pos)> {
+        let (min, max) = self.bounds()?;
+        let width = (max.col - min.col + 1) as usize;
+        let height = (max.row - min.row + 1) as usize;
+        let mut grid = Grid::from_vec(width, height, vec![empty; width * height]);
+        for (&pos, &value) in &self.cells {
+            grid.set(Pos::new(pos.row - min.row, pos.col - min.col), value);
+        }
+        Some((grid, min))
+    }
+}
+
+impl<T> FromIterator<(Pos, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Pos, T)>>(iter: I) -> Self {
+        let mut grid = Self::new();
+        grid.extend(iter);
+        grid
+    }
+}
+
+impl<T> Extend<(Pos, T)> for SparseGrid<T> {
+    fn extend<I: IntoIterator<Item = (Pos, T)>>(&mut self, iter: I) {
+        for (pos, value) in iter {
+            self.insert(pos, value);
+        }
+    }
+}
+
+impl<T> Index<Pos> for SparseGrid<T> {
+    type Output = T;
+
+    fn index(&self, index: Pos) -> &Self::Output {
+        &self.cells[&index]
+    }
+}
+
+/// Grid of bits, with each row packed into 64 bit words. Column `col` of a row is bit `col % 64`
+/// of its word `col / 64`, and the bits past the width in the last word of a row are always 0.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitGrid {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    /// Grid of `width` by `height` zeros
+    #[must_use]
+    pub fn new(width: usize, height: usize) -> Self {
+        let words_per_row = width.div_ceil(64);
+        Self {
+            width,
+            height,
+            words_per_row,
+            words: vec![0; words_per_row * height],
+        }
+    }
+
+    #[must_use]
+    pub const fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub const fn height(&self) -> usize {
+        self.height
+    }
+
+    /// The bit at `pos`, or `false` outside of the grid
+    #[inline]
+    #[must_use]
+    pub fn get(&self, pos: Pos) -> bool {
+        let (Ok(row), Ok(col)) = (usize::try_from(pos.row), usize::try_from(pos.col)) else {
+            return false;
+        };
+        row < self.height
+            && col < self.width
+            && self.words[row * self.words_per_row + col / 64] & (1 << (col % 64)) != 0
+    }
+
+    /// Set the bit at `pos`, if it is inside of the grid
+    #[inline]
+    pub fn set(&mut self, pos: Pos, value: bool) {
+        let (Ok(row), Ok(col)) = (usize::try_from(pos.row), usize::try_from(pos.col)) else {
+            return;
+        };
+        if row < self.height && col < self.width {
+            let word = &mut self.words[row * self.words_per_row + col / 64];
+            if value {
+                *word |= 1 << (col % 64);
+            } else {
+                *word &= !(1 << (col % 64));
+            }
+        }
+    }
+
+    /// Set all bits to 0
+    pub fn clear(&mut self) {
+        self.words.fill(0);
+    }
+
+    #[must_use]
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The words of `row`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is outside of the grid.
+    #[must_use]
+    pub fn row(&self, row: usize) -> &[u64] {
+        assert!(row < self.height, "row {row} outside of the grid");
+        &self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    /// The words of `row`, to change. The bits past the width must be kept 0.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is outside of the grid.
+    pub fn row_mut(&mut self, row: usize) -> &mut [u64] {
+        assert!(row < self.height, "row {row} outside of the grid");
+        &mut self.words[row * self.words_per_row..(row + 1) * self.words_per_row]
+    }
+
+    /// The bits of `col` from top to bottom, packed into words like a row
+    ///
+    /// # Panics
+    ///
+    /// Panics if `col` is outside of the grid.
+    #[must_use]
+    pub fn column(&self, col: usize) -> Vec<u64> {
+        assert!(col < self.width, "column {col} outside of the grid");
+        let mut column = vec![0; self.height.div_ceil(64)];
+        for (row, words) in self.words.chunks_exact(self.words_per_row).enumerate() {
+            column[row / 64] |= (words[col / 64] >> (col % 64) & 1) << (row % 64);
+        }
+        column
+    }
+
+    /// The columns of the set bits of `row`, from left to right
+    pub fn iter_ones(&self, row: usize) -> impl Iterator<Item = usize> + '_ {
+        let words = self.row(row);
+        let mut ix = 0;
+        let mut word = words.first().copied().unwrap_or(0);
+        std::iter::from_fn(move || loop {
+            if word != 0 {
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                return Some(ix * 64 + bit);
+            }
+            ix += 1;
+            word = *words.get(ix)?;
+        })
+    }
+
+    /// Number of set bits in the columns `cols` of `row`
+    #[inline]
+    pub fn count_ones_in(&self, row: usize, cols: impl RangeBounds<usize>) -> usize {
+        let words = self.row(row);
+        Self::masks(bounds(cols, self.width))
+            .map(|(ix, mask)| (words[ix] & mask).count_ones() as usize)
+            .sum()
+    }
+
+    /// Set the columns `cols` of `row` to `value`
+    #[inline]
+    pub fn set_range(&mut self, row: usize, cols: impl RangeBounds<usize>, value: bool) {
+        assert!(row < self.height, "row {row} outside of the grid");
+        let start = row * self.words_per_row;
+        for (ix, mask) in Self::masks(bounds(cols, self.width)) {
+            if value {
+                self.words[start + ix] |= mask;
+            } else {
+                self.words[start + ix] &= !mask;
+            }
+        }
+    }
+
+    /// Set the rows `rows` of `col` to `value`
+    #[inline]
+    pub fn set_column_range(&mut self, col: usize, rows: impl RangeBounds<usize>, value: bool) {
+        assert!(col < self.width, "column {col} outside of the grid");
+        let bit = 1 << (col % 64);
+        let (start, end) = bounds(rows, self.height);
+        for row in start..end {
+            let word = &mut self.words[row * self.words_per_row + col / 64];
+            if value {
+                *word |= bit;
+            } else {
+                *word &= !bit;
+            }
+        }
+    }
+
+    /// Move the bits of `row` `n` columns to the east, dropping the bits that move past the
+    /// width and filling with 0
+    pub fn shift_row_east(&mut self, row: usize, n: usize) {
+        let (words, shift) = (n / 64, n % 64);
+        let tail = self.tail_mask();
+        let row = &mut self.words[row * self.words_per_row..(row + 1) * self.words_per_row];
+        for ix in (0..row.len()).rev() {
+            let low = ix.checked_sub(words).map_or(0, |src| row[src] << shift);
+            let carry = match ix.checked_sub(words + 1) {
+                Some(src) if shift > 0 => row[src] >> (64 - shift),
+                _ => 0,
+            };
+            row[ix] = low | carry;
+        }
+        if let Some(last) = row.last_mut() {
+            *last &= tail;
+        }
+    }
+
+    /// Move the bits of `row` `n` columns to the west, dropping the bits that move past column
+    /// 0 and filling with 0
+    pub fn shift_row_west(&mut self, row: usize, n: usize) {
+        let (words, shift) = (n / 64, n % 64);
+        let row = &mut self.words[row * self.words_per_row..(row + 1) * self.words_per_row];
+        for ix in 0..row.len() {
+            let high = row.get(ix + words).map_or(0, |&src| src >> shift);
+            let carry = match row.get(ix + words + 1) {
+                Some(&src) if shift > 0 => src << (64 - shift),
+                _ => 0,
+            };
+            row[ix] = high | carry;
+        }
+    }
+
+    /// Move all bits `n` columns to the east, see [`BitGrid::shift_row_east`]
+    pub fn shift_east(&mut self, n: usize) {
+        for row in 0..self.height {
+            self.shift_row_east(row, n);
+        }
+    }
+
+    /// Move all bits `n` columns to the west, see [`BitGrid::shift_row_west`]
+    pub fn shift_west(&mut self, n: usize) {
+        for row in 0..self.height {
+            self.shift_row_west(row, n);
+        }
+    }
+
+    /// Move all rows `n` rows to the south, filling the top with 0
+    pub fn shift_south(&mut self, n: usize) {
+        let n = n.min(self.height) * self.words_per_row;
+        let len = self.words.len();
+        self.words.copy_within(..len - n, n);
+        self.words[..n].fill(0);
+    }
+
+    /// Move all rows `n` rows to the north, filling the bottom with 0
+    pub fn shift_north(&mut self, n: usize) {
+        let n = n.min(self.height) * self.words_per_row;
+        let len = self.words.len();
+        self.words.copy_within(n.., 0);
+        self.words[len - n..].fill(0);
+    }
+
+    /// Mask of the columns in use in the last word of a row
+    fn tail_mask(&self) -> u64 {
+        match self.width % 64 {
+            0 => !0,
+            bits => (1 << bits) - 1,
+        }
+    }
+
+    /// Index in the row and mask of each word covering the columns `start..end`
+    #[inline]
+    fn masks((start, end): (usize, usize)) -> impl Iterator<Item = (usize, u64)> {
+        let words = if start < end {
+            start / 64..end.div_ceil(64)
+        } else {
+            0..0
+        };
+        words.map(move |ix| {
+            let low = start.max(ix * 64) - ix * 64;
+            let high = end.min(ix * 64 + 64) - ix * 64;
+            (ix, (!0 >> (64 - (high - low))) << low)
+        })
+    }
+}
+
+/// Start and end of `range`, limited to `0..len`
+#[inline]
+fn bounds(range: impl RangeBounds<usize>, len: usize) -> (usize, usize) {
+    let start = match range.start_bound() {
+        Bound::Included(&start) => start,
+        Bound::Excluded(&start) => start + 1,
+        Bound::Unbounded => 0,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&end) => end + 1,
+        Bound::Excluded(&end) => end,
+        Bound::Unbounded => len,
+    };
+    (start.min(len), end.min(len))
+}
+
+impl Not for BitGrid {
+    type Output = Self;
+
+    fn not(mut self) -> Self {
+        let tail = self.tail_mask();
+        for row in self.words.chunks_exact_mut(self.words_per_row.max(1)) {
+            for word in row.iter_mut() {
+                *word = !*word;
+            }
+            if let Some(last) = row.last_mut() {
+                *last &= tail;
+            }
+        }
+        self
+    }
+}
+
+macro_rules! bitgrid_assign_op {
+    ($trait:ident, $method:ident, $op:tt) => {
+        /// Combine with a grid of the same size, bit by bit
+        impl $trait<&BitGrid> for BitGrid {
+            fn $method(&mut self, other: &BitGrid) {
+                assert_eq!(
+                    (self.width, self.height),
+                    (other.width, other.height),
+                    "grids of different sizes"
+                );
+                for (word, other) in self.words.iter_mut().zip(&other.words) {
+                    *word $op *other;
+                }
+            }
+        }
+    };
+}
+
+bitgrid_assign_op!(BitAndAssign, bitand_assign, &=);
+bitgrid_assign_op!(BitOrAssign, bitor_assign, |=);
+bitgrid_assign_op!(BitXorAssign, bitxor_assign, ^=);
+
+impl Debug for BitGrid {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in 0..self.height {
+            for col in 0..self.width {
+                let pos = Pos::new(isize::try_from(row).unwrap(), isize::try_from(col).unwrap());
+                f.write_char(if self.get(pos) { '#' } else { '.' })?;
+            }
+            f.write_char('\n')?;
+        }
+        Ok(())
+    }
+}
+
+pub fn gcd<T>(mut a: T, mut b: T) -> T
+where
+    T: Copy + Num,
+{
+    let zero = T::zero();
+    while a != zero {
+        let r = b % a;
+        b = a;
+        a = r;
+    }
+    b
+}
+
+pub fn lcm<T>(a: T, b: T) -> T
+where
+    T: Copy + Num,
+{
+    if a.is_zero() || b.is_zero() {
+        return T::zero();
+    }
+    a / gcd(a, b) * b
+}
+
+/// Like [`lcm`], but returns `None` if the result doesn't fit in `T`.
+pub fn checked_lcm<T>(a: T, b: T) -> Option<T>
+where
+    T: PrimInt,
+{
+    if a.is_zero() || b.is_zero() {
+        return Some(T::zero());
+    }
+    (a / gcd(a, b)).checked_mul(&b)
+}
+
+/// The least common multiple of all `values`, computed in `u128`. Returns `None` on overflow.
+/// An empty iterator gives 1.
+pub fn lcm_u128<I>(values: I) -> Option<u128>
+where
+    I: IntoIterator,
+    I::Item: Into<u128>,
+{
+    values
+        .into_iter()
+        .try_fold(1, |acc, value| checked_lcm(acc, value.into()))
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y == g`, where `g` is the non-negative gcd of `a`
+/// and `b`.
+pub fn extended_gcd<T>(a: T, b: T) -> (T, T, T)
+where
+    T: Copy + Num + Signed,
+{
+    let (mut prev_rem, mut rem) = (a, b);
+    let (mut prev_coef_a, mut coef_a) = (T::one(), T::zero());
+    let (mut prev_coef_b, mut coef_b) = (T::zero(), T::one());
+    while !rem.is_zero() {
+        let quot = prev_rem / rem;
+        (prev_rem, rem) = (rem, prev_rem - quot * rem);
+        (prev_coef_a, coef_a) = (coef_a, prev_coef_a - quot * coef_a);
+        (prev_coef_b, coef_b) = (coef_b, prev_coef_b - quot * coef_b);
+    }
+    if prev_rem.is_negative() {
+        (-prev_rem, -prev_coef_a, -prev_coef_b)
+    } else {
+        (prev_rem, prev_coef_a, prev_coef_b)
+    }
+}
+
+/// The inverse of `a` modulo `modulus`, in `0..modulus`, or `None` if they aren't coprime.
+pub fn mod_inverse<T>(a: T, modulus: T) -> Option<T>
+where
+    T: Copy + Num + Signed,
+{
+    let (g, x, _) = extended_gcd(a, modulus);
+    g.is_one().then(|| rem_euclid(x, modulus))
+}
+
+/// `base.pow(exp) % modulus`, without overflowing.
+#[must_use]
+pub fn mod_pow(base: u64, mut exp: u64, modulus: u64) -> u64 {
+    assert!(modulus != 0, "mod_pow: modulus must be non-zero");
+    let modulus = u128::from(modulus);
+    let mut base = u128::from(base) % modulus;
+    let mut res = 1 % modulus;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            res = res * base % modulus;
+        }
+        base = base * base % modulus;
+        exp >>= 1;
+    }
+    u64::try_from(res).unwrap()
+}
+
+/// Solves the system `x ≡ residue (mod modulus)` for all given `(residue, modulus)` pairs. The
+/// moduli don't need to be coprime and the residues may be negative or exceed their moduli.
+///
+/// Returns `(x, lcm)` with `0 <= x < lcm`, so every solution is `x + k * lcm`. Returns `None` if
+/// the congruences contradict each other or the combined modulus overflows. An empty system gives
+/// `(0, 1)`.
+///
+/// # Panics
+/// If any modulus is not positive.
+pub fn crt<I>(congruences: I) -> Option<(i128, i128)>
+where
+    I: IntoIterator<Item = (i128, i128)>,
+{
+    congruences
+        .into_iter()
+        .try_fold((0, 1), |(r1, m1), (r2, m2)| {
+            assert!(m2 > 0, "crt: moduli must be positive");
+            let (gcd_m, coef, _) = extended_gcd(m1, m2);
+            let diff = r2 - r1;
+            if diff % gcd_m != 0 {
+                return None;
+            }
+            // x = r1 + m1 * k, where k = diff / gcd_m * coef (mod m2 / gcd_m).
+            let step = m2 / gcd_m;
+            let k = rem_euclid(diff / gcd_m, step).checked_mul(rem_euclid(coef, step))? % step;
+            let lcm = m1.checked_mul(step)?;
+            Some((rem_euclid(m1.checked_mul(k)?.checked_add(r1)?, lcm), lcm))
+        })
+}
+
+/// Like [`crt`], but the solution is the smallest one that is at least `min`. Useful when the
+/// congruences only hold after some offset, like for cycles that start after a prefix.
+///
+/// # Panics
+/// If any modulus is not positive.
+pub fn crt_from<I>(congruences: I, min: i128) -> Option<(i128, i128)>
+where
+    I: IntoIterator<Item = (i128, i128)>,
+{
+    let (x, lcm) = crt(congruences)?;
+    if x >= min {
+        return Some((x, lcm));
+    }
+    let gap = min - x;
+    let periods = gap / lcm + i128::from(gap % lcm != 0);
+    Some((x.checked_add(periods.checked_mul(lcm)?)?, lcm))
+}
+
+fn rem_euclid<T>(a: T, modulus: T) -> T
+where
+    T: Copy + Num + Signed,
+{
+    let r = a % modulus;
+    if r.is_negative() {
+        r + modulus.abs()
+    } else {
+        r
+    }
+}
+
+/// The states of an iterated simulation, up to the point where it starts repeating. The states,
+/// or their keys, are compared exactly, so hash collisions can't produce a wrong period.
+#[derive(Debug, Clone)]
+pub struct Cycle<S> {
+    states: Vec<S>,
+    prefix_len: usize,
+}
+
+impl<S: Clone> Cycle<S> {
+    /// Applies `step` to the initial `state` until a state repeats. Loops forever if no state ever repeats.
+    pub fn find(state: S, step: impl FnMut(&mut S)) -> Self
+    where
+        S: Eq + Hash,
+    {
+        Self::find_by_key(state, step, S::clone)
+    }
+
+    /// Like [`Cycle::find`], but compares the states by `key(state)`. The key must include
+    /// everything that `step` changes or depends on, while the parts of the state that never
+    /// change can be left out of it.
+    pub fn find_by_key<K: Eq + Hash>(
+        mut state: S,
+        mut step: impl FnMut(&mut S),
+        mut key: impl FnMut(&S) -> K,
+    ) -> Self {
+        let mut seen = HashMap::new();
+        let mut states = Vec::new();
+        let prefix_len = loop {
+            match seen.entry(key(&state)) {
+                Entry::Occupied(entry) => break *entry.get(),
+                Entry::Vacant(entry) => entry.insert(states.len()),
+            };
+            states.push(state.clone());
+            step(&mut state);
+        };
+        Self { states, prefix_len }
+    }
+}
+
+impl<S> Cycle<S> {
+    /// Number of steps before the first state of the cycle.
+    #[must_use]
+    pub fn prefix_len(&self) -> usize {
+        self.prefix_len
+    }
+
+    /// Number of steps after which the states repeat.
+    #[must_use]
+    pub fn period(&self) -> usize {
+        self.states.len() - self.prefix_len
+    }
+
+    /// The state after `n` steps, without simulating them.
+    #[must_use]
+    pub fn nth(&self, n: usize) -> &S {
+        if n < self.prefix_len {
+            &self.states[n]
+        } else {
+            &self.states[self.prefix_len + (n - self.prefix_len) % self.period()]
+        }
+    }
+}
+
+/// Errors from the common parsers. Offsets are in bytes, from the start of the text given to the
+/// parser that failed. [`crate::diagnostic::Location::of_error`] turns them into a line and column.
+#[derive(Debug, Clone, Error)]
+pub enum CommonParseError {
+    #[error("Input is empty")]
+    EmptyInput,
+    #[error("Expected char: {0:?}")]
+    ExpectedChar(char),
+    #[error("Invalid direction: {0:?}")]
+    InvalidDirection(char),
+    /// The text did not match. `offset` is where the first byte that differs is.
+    #[error("Expected {expected:?}")]
+    ExpectedText { expected: String, offset: usize },
+    #[error("Expected an identifier")]
+    ExpectedIdent { offset: usize },
+    #[error("Unexpected end of line")]
+    UnexpectedEnd { offset: usize },
+    #[error("Unexpected text at the end of the line")]
+    TrailingText { offset: usize },
+    #[error("Invalid integer: {0}")]
+    InvalidInteger(#[from] ParseIntError),
+    #[error("{0}")]
+    InvalidInteger2(#[from] ParseIntError2),
+    #[error("Invalid float: {0}")]
+    InvalidFloat(#[from] ParseFloatError),
+    /// A line of a grid is empty. `offset` is where the line starts.
+    #[error("Line {line} of the grid is empty")]
+    EmptyLine { line: usize, offset: usize },
+    /// A line of a grid has a different width than the first line. `offset` is where the line is
+    /// cut short, or where the extra cells begin.
+    #[error("Line {line} of the grid has {found} cells, expected {expected}")]
+    RaggedLine {
+        line: usize,
+        expected: usize,
+        found: usize,
+        offset: usize,
+    },
+    /// A grid cell was rejected. `offset` is where the cell is.
+    #[error("{source}")]
+    InvalidCell { offset: usize, source: SharedError },
+    /// A part of the text, such as a line, was rejected. `offset` is where the part starts, and
+    /// the location of `source`, if it has one, is from the start of the part.
+    #[error("{source}")]
+    InvalidPart { offset: usize, source: SharedError },
+}
+
+/// An error shared between the clones of the error that wraps it. Its source is the shared
+/// error, so that walking the sources still finds it.
+#[derive(Debug, Clone)]
+pub struct SharedError(Arc<dyn std::error::Error + Send + Sync>);
+
+impl Display for SharedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for SharedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&*self.0)
+    }
+}
+
+impl CommonParseError {
+    /// Wrap the error from converting the byte at `offset` into a grid cell
+    pub fn invalid_cell<E>(err: E, offset: usize) -> E
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        Self: Into<E>,
+    {
+        Self::InvalidCell {
+            offset,
+            source: SharedError(Arc::new(err)),
+        }
+        .into()
+    }
+
+    /// Wrap the error from parsing the part of the text starting at `offset`
+    pub fn invalid_part<E>(err: E, offset: usize) -> E
+    where
+        E: std::error::Error + Send + Sync + 'static,
+        Self: Into<E>,
+    {
+        Self::InvalidPart {
+            offset,
+            source: SharedError(Arc::new(err)),
+        }
+        .into()
+    }
+
+    /// Where the error is, if it is known. Errors in the `source` of this one are relative to it.
+    #[must_use]
+    pub fn offset(&self) -> Option<usize> {
+        match self {
+            Self::ExpectedText { offset, .. }
+            | Self::ExpectedIdent { offset }
+            | Self::UnexpectedEnd { offset }
+            | Self::TrailingText { offset }
+            | Self::EmptyLine { offset, .. }
+            | Self::RaggedLine { offset, .. }
+            | Self::InvalidCell { offset, .. }
+            | Self::InvalidPart { offset, .. } => Some(*offset),
+            _ => None,
+        }
+    }
+
+    /// The same error, for text that starts `shift` bytes further into the input
+    #[must_use]
+    pub fn offset_by(mut self, shift: usize) -> Self {
+        match &mut self {
+            Self::ExpectedText { offset, .. }
+            | Self::ExpectedIdent { offset }
+            | Self::UnexpectedEnd { offset }
+            | Self::TrailingText { offset }
+            | Self::EmptyLine { offset, .. }
+            | Self::RaggedLine { offset, .. }
+            | Self::InvalidCell { offset, .. }
+            | Self::InvalidPart { offset, .. } => *offset += shift,
+            Self::InvalidInteger2(err) => err.offset += shift,
+            _ => (),
+        }
+        self
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Coordinate<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Coordinate2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+/// Implement the operations shared by [`Coordinate`] and [`Coordinate2`], field by field
+macro_rules! coordinate {
+    ($name:ident { $first:ident $(, $field:ident)* }) => {
+        impl<T> $name<T> {
+            pub fn new($first: T $(, $field: T)*) -> Self {
+                Self { $first $(, $field)* }
+            }
+
+            /// Apply `f` to each field
+            pub fn map<U>(self, mut f: impl FnMut(T) -> U) -> $name<U> {
+                $name {
+                    $first: f(self.$first),
+                    $($field: f(self.$field),)*
+                }
+            }
+
+            /// Combine the fields of `self` and `other` pairwise
+            pub fn zip_with<U, V>(self, other: $name<U>, mut f: impl FnMut(T, U) -> V) -> $name<V> {
+                $name {
+                    $first: f(self.$first, other.$first),
+                    $($field: f(self.$field, other.$field),)*
+                }
+            }
+        }
+
+        impl<T> $name<T>
+        where
+            T: Ord,
+        {
+            #[must_use]
+            pub fn min_fields(self, other: Self) -> Self {
+                self.zip_with(other, T::min)
+            }
+
+            #[must_use]
+            pub fn max_fields(self, other: Self) -> Self {
+                self.zip_with(other, T::max)
+            }
+        }
+
+        impl<T> $name<T>
+        where
+            T: Num + Copy,
+        {
+            pub fn dot(self, other: Self) -> T {
+                self.$first * other.$first $(+ self.$field * other.$field)*
+            }
+        }
+
+        impl<T> $name<T>
+        where
+            T: Signed + Ord + Copy,
+        {
+            /// Sum of the absolute values of the fields, the L1 norm
+            pub fn manhattan_norm(self) -> T {
+                self.$first.abs() $(+ self.$field.abs())*
+            }
+
+            /// Largest absolute value of the fields, the L∞ norm
+            pub fn max_norm(self) -> T {
+                self.$first.abs() $(.max(self.$field.abs()))*
+            }
+        }
+
+        impl<T> Debug for $name<T>
+        where
+            T: Debug,
+        {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.debug_tuple("")
+                    .field(&self.$first)
+                    $(.field(&self.$field))*
+                    .finish()
+            }
+        }
+
+        /// Formats as the fields separated by `", "`, the way most puzzles write them
+        impl<T> Display for $name<T>
+        where
+            T: Display,
+        {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                write!(f, "{}", self.$first)?;
+                $(write!(f, ", {}", self.$field)?;)*
+                Ok(())
+            }
+        }
+
+        /// Parses integer fields separated by commas, with optional whitespace around each field
+        impl<'a, T> TryFrom<&'a [u8]> for $name<T>
+        where
+            T: PrimInt,
+        {
+            type Error = CommonParseError;
+
+            fn try_from(s: &'a [u8]) -> Result<Self, Self::Error> {
+                let mut parts = s.split(|&ch| ch == b',');
+                let mut offset = 0;
+                let mut next = || -> Result<T, CommonParseError> {
+                    let part = parts.next().ok_or(CommonParseError::ExpectedChar(','))?;
+                    let start = offset + part.len() - part.trim_start().len();
+                    let value = parse_int(part.trim()).map_err(|err| err.offset_by(start))?;
+                    offset += part.len() + 1;
+                    Ok(value)
+                };
+                let coordinate = Self {
+                    $first: next()?,
+                    $($field: next()?,)*
+                };
+                match parts.next() {
+                    // Point at the extra comma
+                    Some(_) => Err(CommonParseError::TrailingText { offset: offset - 1 }),
+                    None => Ok(coordinate),
+                }
+            }
+        }
+
+        impl<T> Add for $name<T>
+        where
+            T: Add<T, Output = T>,
+        {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                self.zip_with(rhs, T::add)
+            }
+        }
+
+        impl<T> Sub for $name<T>
+        where
+            T: Sub<T, Output = T>,
+        {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                self.zip_with(rhs, T::sub)
+            }
+        }
+
+        impl<T> Neg for $name<T>
+        where
+            T: Neg<Output = T>,
+        {
+            type Output = Self;
+
+            fn neg(self) -> Self::Output {
+                self.map(T::neg)
+            }
+        }
+
+        impl<T> Mul<T> for $name<T>
+        where
+            T: Mul<T, Output = T> + Copy,
+        {
+            type Output = Self;
+
+            fn mul(self, rhs: T) -> Self::Output {
+                self.map(|value| value * rhs)
+            }
+        }
+
+        impl<T> Div<T> for $name<T>
+        where
+            T: Div<T, Output = T> + Copy,
+        {
+            type Output = Self;
+
+            fn div(self, rhs: T) -> Self::Output {
+                self.map(|value| value / rhs)
+            }
+        }
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self {
-            x: self.x - rhs.x,
-            y: self.y - rhs.y,
-            z: self.z - rhs.z,
+        impl<T> AddAssign for $name<T>
+        where
+            T: AddAssign,
+        {
+            fn add_assign(&mut self, rhs: Self) {
+                self.$first += rhs.$first;
+                $(self.$field += rhs.$field;)*
+            }
         }
-    }
+
+        impl<T> SubAssign for $name<T>
+        where
+            T: SubAssign,
+        {
+            fn sub_assign(&mut self, rhs: Self) {
+                self.$first -= rhs.$first;
+                $(self.$field -= rhs.$field;)*
+            }
+        }
+
+        impl<T> MulAssign<T> for $name<T>
+        where
+            T: MulAssign + Copy,
+        {
+            fn mul_assign(&mut self, rhs: T) {
+                self.$first *= rhs;
+                $(self.$field *= rhs;)*
+            }
+        }
+
+        impl<T> DivAssign<T> for $name<T>
+        where
+            T: DivAssign + Copy,
+        {
+            fn div_assign(&mut self, rhs: T) {
+                self.$first /= rhs;
+                $(self.$field /= rhs;)*
+            }
+        }
+    };
 }
 
-impl<T> Mul<T> for Coordinate<T>
+coordinate!(Coordinate { x, y, z });
+coordinate!(Coordinate2 { x, y });
+
+impl<T> Coordinate<T>
 where
-    T: Mul<T, Output = T> + Copy,
+    T: Num + Copy,
 {
-    type Output = Self;
-
-    fn mul(self, rhs: T) -> Self::Output {
+    #[must_use]
+    pub fn cross(self, other: Self) -> Self {
         Self {
-            x: self.x * rhs,
-            y: self.y * rhs,
-            z: self.z * rhs,
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
         }
     }
 }
 
-impl<T> Div<T> for Coordinate<T>
+impl<T> Coordinate<T> {
+    /// Drop the z field, projecting onto the xy plane
+    pub fn xy(self) -> Coordinate2<T> {
+        Coordinate2::new(self.x, self.y)
+    }
+}
+
+impl<T> Coordinate2<T>
 where
-    T: Div<T, Output = T> + Copy,
+    T: Num + Copy,
 {
-    type Output = Self;
-
-    fn div(self, rhs: T) -> Self::Output {
-        Self {
-            x: self.x / rhs,
-            y: self.y / rhs,
-            z: self.z / rhs,
-        }
+    /// The z field of the cross product of `self` and `other` in the xy plane. It is positive
+    /// if `other` is counterclockwise from `self`, when y grows upwards.
+    pub fn cross(self, other: Self) -> T {
+        self.x * other.y - self.y * other.x
     }
 }
 
-#[derive(Debug, Error)]
-#[error("Invalid integer")]
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("Invalid integer: {}", describe_int_error(*kind))]
 pub struct ParseIntError2 {
     pub kind: IntErrorKind,
+    /// Offset of the offending byte from the start of the parsed text, see
+    /// [`crate::diagnostic::Location`]
+    pub offset: usize,
+}
+
+fn describe_int_error(kind: IntErrorKind) -> &'static str {
+    match kind {
+        IntErrorKind::Empty => "no digits",
+        IntErrorKind::InvalidDigit => "invalid digit",
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => "out of range",
+        _ => "unknown error",
+    }
 }
 
 impl ParseIntError2 {
-    fn new(kind: IntErrorKind) -> Self {
-        Self { kind }
+    fn new(kind: IntErrorKind, offset: usize) -> Self {
+        Self { kind, offset }
+    }
+
+    /// The same error, for text that starts `shift` bytes further into the input
+    #[must_use]
+    pub fn offset_by(mut self, shift: usize) -> Self {
+        self.offset += shift;
+        self
+    }
+}
+
+/// The lines of `text`, split like [`ByteSlice::lines`], with the offset where each line starts
+pub fn lines_with_offsets(text: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    text.lines_with_terminator().map(move |line| {
+        let start = offset;
+        offset += line.len();
+        let line = match line.strip_suffix(b"\n") {
+            Some(line) => line.strip_suffix(b"\r").unwrap_or(line),
+            None => line,
+        };
+        (start, line)
+    })
+}
+
+/// The lines of a grid, with the offset where each line starts.
+///
+/// # Errors
+///
+/// Fails unless there is at least one line, and all lines are equally long and not empty.
+pub fn grid_lines(text: &[u8]) -> Result<Vec<(usize, &[u8])>, CommonParseError> {
+    let mut lines = Vec::new();
+    for (ix, (offset, line)) in lines_with_offsets(text).enumerate() {
+        let expected = lines
+            .first()
+            .map_or(line.len(), |(_, first): &(_, &[u8])| first.len());
+        if line.is_empty() {
+            return Err(CommonParseError::EmptyLine {
+                line: ix + 1,
+                offset,
+            });
+        }
+        if line.len() != expected {
+            return Err(CommonParseError::RaggedLine {
+                line: ix + 1,
+                expected,
+                found: line.len(),
+                offset: offset + expected.min(line.len()),
+            });
+        }
+        lines.push((offset, line));
+    }
+    if lines.is_empty() {
+        return Err(CommonParseError::EmptyInput);
     }
+    Ok(lines)
+}
+
+/// The non-empty runs of bytes between ASCII whitespace in `text`, with the offset where each
+/// run starts
+pub fn fields_with_offsets(text: &[u8]) -> impl Iterator<Item = (usize, &[u8])> {
+    let mut offset = 0;
+    text.split(u8::is_ascii_whitespace)
+        .map(move |field| {
+            let start = offset;
+            offset += field.len() + 1;
+            (start, field)
+        })
+        .filter(|(_, field)| !field.is_empty())
 }
 
+/// Parse a decimal integer, see [`parse_int_radix`]
+///
+/// # Errors
+///
+/// Fails if `s` is not a number that fits in `T`.
 pub fn parse_int<T: PrimInt>(s: &[u8]) -> Result<T, ParseIntError2> {
     parse_int_radix(s, T::from(10_u8).unwrap())
 }
 
-pub fn parse_int_radix<T: PrimInt>(mut s: &[u8], base: T) -> Result<T, ParseIntError2> {
-    debug_assert!(
-        base <= T::from(10).unwrap(),
-        "Bases above 10 is not implemented"
-    );
-    debug_assert!(base >= T::from(2).unwrap(), "Bases below 2 is not invalid");
-    loop {
-        match s {
-            [] | [b'-'] => return Err(ParseIntError2::new(IntErrorKind::Empty)),
-            [b' ', rest @ ..] => s = rest,
-            [b'-', dig @ b'0'..=b'9', rest @ ..] => {
-                let dig = T::from(dig - b'0').unwrap();
-                #[cfg(debug_assertions)]
-                if dig >= base {
-                    return Err(ParseIntError2::new(IntErrorKind::InvalidDigit));
-                }
-                let mut value = T::zero()
-                    .checked_sub(&dig)
-                    .ok_or(ParseIntError2::new(IntErrorKind::NegOverflow))?;
-                s = rest;
-                loop {
-                    match s {
-                        [] => return Ok(value),
-                        [dig @ b'0'..=b'9', rest @ ..] => {
-                            let dig = T::from(dig - b'0').unwrap();
-                            #[cfg(debug_assertions)]
-                            if dig >= base {
-                                return Err(ParseIntError2::new(IntErrorKind::InvalidDigit));
-                            }
-                            s = rest;
-                            value = value
-                                .checked_mul(&base)
-                                .ok_or(ParseIntError2::new(IntErrorKind::NegOverflow))?
-                                .checked_sub(&dig)
-                                .ok_or(ParseIntError2::new(IntErrorKind::NegOverflow))?;
-                        }
-                        rest if rest.find_not_byteset(b" ").is_none() => return Ok(value),
-                        _ => return Err(ParseIntError2::new(IntErrorKind::InvalidDigit)),
-                    }
-                }
+/// Parse an integer in `base`, from 2 to 36, with the digits above 9 as letters in either case.
+/// The digits may follow a `+` sign, or a `-` sign for signed types, and in base 16, 8 and 2 a
+/// `0x`, `0o` or `0b` prefix. Nothing else is allowed, not even surrounding whitespace.
+///
+/// # Errors
+///
+/// Fails if `s` is not a number that fits in `T`, with the offset of the first byte that is
+/// wrong.
+///
+/// # Panics
+///
+/// Panics if `base` is outside of `2..=36`.
+pub fn parse_int_radix<T: PrimInt>(s: &[u8], base: T) -> Result<T, ParseIntError2> {
+    let radix = check_radix(base);
+    let (negative, digits) = match s {
+        [b'-', rest @ ..] if T::min_value() < T::zero() => (true, rest),
+        [b'+', rest @ ..] => (false, rest),
+        _ => (false, s),
+    };
+    let digits = match (radix, digits) {
+        (16, [b'0', b'x' | b'X', rest @ ..])
+        | (8, [b'0', b'o' | b'O', rest @ ..])
+        | (2, [b'0', b'b' | b'B', rest @ ..]) => rest,
+        _ => digits,
+    };
+    parse_digits(digits, s.len() - digits.len(), negative, base, radix)
+}
+
+fn check_radix<T: PrimInt>(base: T) -> u32 {
+    base.to_u32()
+        .filter(|radix| (2..=36).contains(radix))
+        .expect("base must be from 2 to 36")
+}
+
+/// The value of the non-empty `digits`, which start at offset `start`
+fn parse_digits<T: PrimInt>(
+    digits: &[u8],
+    start: usize,
+    negative: bool,
+    base: T,
+    radix: u32,
+) -> Result<T, ParseIntError2> {
+    if digits.is_empty() {
+        return Err(ParseIntError2::new(IntErrorKind::Empty, start));
+    }
+    let mut value = T::zero();
+    for (ix, &ch) in digits.iter().enumerate() {
+        let at = start + ix;
+        let digit = match ch {
+            b'0'..=b'9' => ch - b'0',
+            b'a'..=b'z' => ch - b'a' + 10,
+            b'A'..=b'Z' => ch - b'A' + 10,
+            _ => u8::MAX,
+        };
+        if u32::from(digit) >= radix {
+            return Err(ParseIntError2::new(IntErrorKind::InvalidDigit, at));
+        }
+        // Digits are below 36, so they fit in any integer type
+        let digit = T::from(digit).unwrap();
+        value = if negative {
+            value
+                .checked_mul(&base)
+                .and_then(|value| value.checked_sub(&digit))
+                .ok_or(ParseIntError2::new(IntErrorKind::NegOverflow, at))?
+        } else {
+            value
+                .checked_mul(&base)
+                .and_then(|value| value.checked_add(&digit))
+                .ok_or(ParseIntError2::new(IntErrorKind::PosOverflow, at))?
+        };
+    }
+    Ok(value)
+}
+
+/// Cursor for parsing a line piece by piece. Each method consumes what it matched, and fails
+/// with an error located at the first byte that did not match. See [`scan!`] for matching a
+/// whole line at once.
+#[derive(Debug, Clone, Copy)]
+pub struct Scanner<'a> {
+    rest: &'a [u8],
+    /// Number of bytes consumed so far
+    offset: usize,
+}
+
+impl<'a> Scanner<'a> {
+    #[must_use]
+    pub fn new(text: &'a [u8]) -> Self {
+        Self {
+            rest: text,
+            offset: 0,
+        }
+    }
+
+    /// The text that is not consumed yet
+    #[must_use]
+    pub fn rest(&self) -> &'a [u8] {
+        self.rest
+    }
+
+    /// Consume the literal text `expected`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text does not start with `expected`.
+    pub fn literal(&mut self, expected: &str) -> Result<(), CommonParseError> {
+        if self.rest.starts_with(expected.as_bytes()) {
+            self.take(expected.len());
+            return Ok(());
+        }
+        // Point at the first byte that differs
+        let matching = self
+            .rest
+            .iter()
+            .zip(expected.as_bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        Err(CommonParseError::ExpectedText {
+            expected: expected.to_string(),
+            offset: self.offset + matching,
+        })
+    }
+
+    /// A non-empty run of ASCII letters, digits and underscores
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text does not start with one.
+    pub fn ident(&mut self) -> Result<&'a [u8], CommonParseError> {
+        let len = self
+            .rest
+            .iter()
+            .position(|&ch| !(ch.is_ascii_alphanumeric() || ch == b'_'))
+            .unwrap_or(self.rest.len());
+        if len == 0 {
+            return Err(CommonParseError::ExpectedIdent {
+                offset: self.offset,
+            });
+        }
+        Ok(self.take(len))
+    }
+
+    /// A decimal integer, with a `-` sign for signed types
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text does not start with one that fits in `T`.
+    pub fn int<T: PrimInt>(&mut self) -> Result<T, CommonParseError> {
+        let sign = usize::from(self.rest.first() == Some(&b'-'));
+        let len = sign
+            + self.rest[sign..]
+                .iter()
+                .position(|ch| !ch.is_ascii_digit())
+                .unwrap_or(self.rest.len() - sign);
+        let value = parse_int(&self.rest[..len]).map_err(|err| err.offset_by(self.offset))?;
+        self.take(len);
+        Ok(value)
+    }
+
+    /// Integers separated by `separator`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the text does not start with an integer, or one of them does not fit in `T`.
+    pub fn ints<T: PrimInt>(&mut self, separator: &str) -> Result<Vec<T>, CommonParseError> {
+        let mut values = vec![self.int()?];
+        while self.rest.starts_with(separator.as_bytes()) {
+            self.take(separator.len());
+            values.push(self.int()?);
+        }
+        Ok(values)
+    }
+
+    /// A single byte
+    ///
+    /// # Errors
+    ///
+    /// Fails at the end of the text.
+    pub fn byte(&mut self) -> Result<u8, CommonParseError> {
+        match self.rest.first() {
+            Some(&ch) => {
+                self.take(1);
+                Ok(ch)
             }
-            [dig @ b'0'..=b'9', rest @ ..] => {
-                let dig = T::from(dig - b'0').unwrap();
-                #[cfg(debug_assertions)]
-                if dig >= base {
-                    return Err(ParseIntError2::new(IntErrorKind::InvalidDigit));
-                }
-                let mut value = dig;
-                s = rest;
-                loop {
-                    match s {
-                        [] => return Ok(value),
-                        [dig @ b'0'..=b'9', rest @ ..] => {
-                            let dig = T::from(dig - b'0').unwrap();
-                            #[cfg(debug_assertions)]
-                            if dig >= base {
-                                return Err(ParseIntError2::new(IntErrorKind::InvalidDigit));
-                            }
-                            s = rest;
-                            value = value
-                                .checked_mul(&base)
-                                .ok_or(ParseIntError2::new(IntErrorKind::NegOverflow))?
-                                .checked_add(&dig)
-                                .ok_or(ParseIntError2::new(IntErrorKind::NegOverflow))?;
-                        }
-                        rest if rest.find_not_byteset(b" ").is_none() => return Ok(value),
-                        _ => return Err(ParseIntError2::new(IntErrorKind::InvalidDigit)),
-                    }
-                }
+            None => Err(CommonParseError::UnexpectedEnd {
+                offset: self.offset,
+            }),
+        }
+    }
+
+    /// The text up to the next `end`, which is not consumed
+    ///
+    /// # Errors
+    ///
+    /// Fails if `end` is not found.
+    pub fn until(&mut self, end: &str) -> Result<&'a [u8], CommonParseError> {
+        match self.rest.find(end) {
+            Some(len) => Ok(self.take(len)),
+            None => Err(CommonParseError::ExpectedText {
+                expected: end.to_string(),
+                offset: self.offset + self.rest.len(),
+            }),
+        }
+    }
+
+    /// All of the remaining text
+    pub fn tail(&mut self) -> &'a [u8] {
+        self.take(self.rest.len())
+    }
+
+    /// Check that all of the text is consumed
+    ///
+    /// # Errors
+    ///
+    /// Fails if there is text left.
+    pub fn end(&self) -> Result<(), CommonParseError> {
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            Err(CommonParseError::TrailingText {
+                offset: self.offset,
+            })
+        }
+    }
+
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        self.offset += len;
+        taken
+    }
+}
+
+/// Match all of `text` against a sequence of literal strings and captures, returning a
+/// `Result` with a tuple of the captured values, or the [`CommonParseError`] of the first part
+/// that did not match. The captures are the methods of [`Scanner`]: `ident`, `int`,
+/// `ints(separator)`, `byte`, `until(end)` and `tail`.
+///
+/// ```
+/// use aoc_rust_2023::aoclib::{scan, CommonParseError};
+///
+/// let (name, left, right) = scan!(b"AAA = (BBB, CCC)" => ident " = (" ident ", " ident ")")?;
+/// assert_eq!([name, left, right], [b"AAA", b"BBB", b"CCC"]);
+///
+/// let (name, rules) = scan!(b"px{a<2006:qkq,rfg}" => ident "{" until("}") "}")?;
+/// assert_eq!((name, rules), (&b"px"[..], &b"a<2006:qkq,rfg"[..]));
+///
+/// let (x, y): (u8, i32) = scan!(b"x=3, y=-4" => "x=" int ", y=" int)?;
+/// assert_eq!((x, y), (3, -4));
+///
+/// let err = scan!(b"x=3; y=-4" => "x=" int ", y=" int).map(|_: (u8, i32)| ()).unwrap_err();
+/// assert_eq!(err.offset(), Some(3));
+/// # Ok::<(), CommonParseError>(())
+/// ```
+///
+/// [`CommonParseError`]: crate::aoclib::CommonParseError
+/// [`Scanner`]: crate::aoclib::Scanner
+#[macro_export]
+macro_rules! scan {
+    // Literals are matched before the next capture, or after the last one
+    (@munch $s:ident [$($pending:tt)*] [$($out:tt)*] $literal:literal $($rest:tt)*) => {
+        $crate::aoclib::scan!(@munch $s [$($pending)* $s.literal($literal)?;] [$($out)*] $($rest)*)
+    };
+    (@munch $s:ident [$($pending:tt)*] [$($out:tt)*] tail $($rest:tt)*) => {
+        $crate::aoclib::scan!(@munch $s [] [$($out)* { $($pending)* $s.tail() },] $($rest)*)
+    };
+    (@munch $s:ident [$($pending:tt)*] [$($out:tt)*] $capture:ident ($($args:expr),*) $($rest:tt)*) => {
+        $crate::aoclib::scan!(@munch $s [] [$($out)* { $($pending)* $s.$capture($($args),*)? },] $($rest)*)
+    };
+    (@munch $s:ident [$($pending:tt)*] [$($out:tt)*] $capture:ident $($rest:tt)*) => {
+        $crate::aoclib::scan!(@munch $s [] [$($out)* { $($pending)* $s.$capture()? },] $($rest)*)
+    };
+    (@munch $s:ident [$($pending:tt)*] [$($out:tt)*]) => {{
+        let out = ($($out)*);
+        $($pending)*
+        $s.end()?;
+        out
+    }};
+    ($text:expr => $($pattern:tt)*) => {
+        (|| -> Result<_, $crate::aoclib::CommonParseError> {
+            let mut scanner = $crate::aoclib::Scanner::new($text);
+            Ok($crate::aoclib::scan!(@munch scanner [] [] $($pattern)*))
+        })()
+    };
+}
+
+pub use scan;
+
+/// The blank line separated sections of `text`, without their final line endings. Empty
+/// sections are skipped.
+pub fn sections(text: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = text;
+    std::iter::from_fn(move || loop {
+        if rest.is_empty() {
+            return None;
+        }
+        let mut len = 0;
+        let mut next = rest.len();
+        for line in rest.split_inclusive(|&ch| ch == b'\n') {
+            if line.trim_end_with(|ch| ch == '\r' || ch == '\n').is_empty() {
+                next = len + line.len();
+                break;
+            }
+            len += line.len();
+        }
+        let section = rest[..len].trim_end_with(|ch| ch == '\r' || ch == '\n');
+        rest = &rest[next..];
+        if !section.is_empty() {
+            return Some(section);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grid with the `#` cells of `rows` set
+    fn bit_grid(rows: &[&str]) -> BitGrid {
+        let mut grid = BitGrid::new(rows[0].len(), rows.len());
+        for (row, line) in rows.iter().enumerate() {
+            for (col, ch) in line.bytes().enumerate() {
+                grid.set_range(row, col..=col, ch == b'#');
+            }
+        }
+        grid
+    }
+
+    #[test]
+    fn bit_grid_shifts_rows_across_words() {
+        let mut grid = BitGrid::new(130, 1);
+        grid.set_range(0, 60..70, true);
+        grid.set_range(0, 127..130, true);
+        grid.shift_row_east(0, 65);
+        assert_eq!(
+            grid.iter_ones(0).collect::<Vec<_>>(),
+            (125..130).collect::<Vec<_>>()
+        );
+        grid.shift_row_west(0, 125);
+        assert_eq!(
+            grid.iter_ones(0).collect::<Vec<_>>(),
+            (0..5).collect::<Vec<_>>()
+        );
+        grid.shift_row_west(0, 200);
+        assert_eq!(grid.count_ones(), 0);
+    }
+
+    #[test]
+    fn bit_grid_shifts_rows() {
+        let mut grid = bit_grid(&["#..", ".#.", "..#"]);
+        grid.shift_south(1);
+        assert_eq!(grid, bit_grid(&["...", "#..", ".#."]));
+        grid.shift_north(2);
+        assert_eq!(grid, bit_grid(&[".#.", "...", "..."]));
+        grid.shift_east(1);
+        assert_eq!(grid, bit_grid(&["..#", "...", "..."]));
+        grid.shift_south(5);
+        assert_eq!(grid.count_ones(), 0);
+    }
+
+    #[test]
+    fn bit_grid_masks_ranges() {
+        let mut grid = BitGrid::new(200, 2);
+        grid.set_range(1, 10..=140, true);
+        assert_eq!(grid.count_ones_in(1, ..), 131);
+        assert_eq!(grid.count_ones_in(1, 64..128), 64);
+        assert_eq!(grid.count_ones_in(1, 140..), 1);
+        assert_eq!(grid.count_ones_in(1, 5..5), 0);
+        assert_eq!(grid.count_ones_in(0, ..), 0);
+        grid.set_range(1, 63..65, false);
+        assert_eq!(grid.count_ones_in(1, 60..70), 8);
+        assert_eq!(grid.row(1)[3], 0);
+    }
+
+    #[test]
+    fn bit_grid_not_keeps_tail_clear() {
+        let grid = !BitGrid::new(70, 3);
+        assert_eq!(grid.count_ones(), 210);
+        assert_eq!(grid.row(2)[1], (1 << 6) - 1);
+        let mut grid = grid;
+        grid.shift_row_east(0, 1);
+        assert_eq!(grid.count_ones_in(0, ..), 69);
+    }
+
+    #[test]
+    fn bit_grid_column() {
+        let mut grid = BitGrid::new(3, 70);
+        grid.set_column_range(1, 2..68, true);
+        grid.set_range(0, .., true);
+        assert_eq!(grid.column(1), vec![!0b10, 0b1111]);
+        assert_eq!(grid.column(2), vec![1, 0]);
+    }
+
+    #[test]
+    fn extended_gcd_bezout() {
+        for (a, b) in [(240, 46), (46, 240), (-240, 46), (7, 0), (0, 7), (17, 17)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(i64::abs(a), i64::abs(b)), "gcd of {a} and {b}");
+            assert_eq!(a * x + b * y, g, "coefficients of {a} and {b}");
+        }
+    }
+
+    #[test]
+    fn mod_inverse_only_for_coprimes() {
+        assert_eq!(mod_inverse(3, 11), Some(4));
+        assert_eq!(mod_inverse(-3, 11), Some(7));
+        assert_eq!(mod_inverse(1, 1), Some(0));
+        assert_eq!(mod_inverse(6, 9), None);
+    }
+
+    #[test]
+    fn crt_coprime_moduli() {
+        assert_eq!(crt([(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt([(-1, 4), (12, 5)]), Some((7, 20)));
+        assert_eq!(crt([]), Some((0, 1)));
+    }
+
+    #[test]
+    fn crt_non_coprime_moduli() {
+        assert_eq!(crt([(2, 6), (8, 10)]), Some((8, 30)));
+        assert_eq!(crt([(1, 6), (2, 4)]), None);
+        assert_eq!(crt([(5, 12), (5, 12)]), Some((5, 12)));
+    }
+
+    #[test]
+    fn crt_from_offset() {
+        assert_eq!(crt_from([(2, 3), (3, 5)], 0), Some((8, 15)));
+        assert_eq!(crt_from([(2, 3), (3, 5)], 8), Some((8, 15)));
+        assert_eq!(crt_from([(2, 3), (3, 5)], 9), Some((23, 15)));
+        assert_eq!(crt_from([(5, 4), (13, 8)], 20), Some((21, 8)));
+        assert_eq!(crt_from([(1, 6), (2, 4)], 0), None);
+    }
+
+    #[test]
+    fn crt_overflow() {
+        let prime = (1 << 61) - 1;
+        assert_eq!(crt([(1, prime), (2, prime - 2), (3, prime - 4)]), None);
+        assert_eq!(checked_lcm(u64::MAX, u64::MAX - 1), None);
+        assert_eq!(checked_lcm(4_u64, 6), Some(12));
+    }
+
+    /// The kind and offset of the error of a parse that must fail
+    fn int_error<T: PrimInt + Debug>(result: Result<T, ParseIntError2>) -> (IntErrorKind, usize) {
+        let err = result.expect_err("invalid integer");
+        (err.kind, err.offset)
+    }
+
+    #[test]
+    fn parse_int_signs() {
+        assert_eq!(parse_int::<i32>(b"-42"), Ok(-42));
+        assert_eq!(parse_int::<u8>(b"0"), Ok(0));
+        assert_eq!(
+            int_error(parse_int::<u32>(b"-1")),
+            (IntErrorKind::InvalidDigit, 0)
+        );
+        assert_eq!(parse_int::<u32>(b"+1"), Ok(1));
+        assert_eq!(
+            int_error(parse_int::<i32>(b" 1")),
+            (IntErrorKind::InvalidDigit, 0)
+        );
+        assert_eq!(int_error(parse_int::<i32>(b"-")), (IntErrorKind::Empty, 1));
+        assert_eq!(int_error(parse_int::<i32>(b"")), (IntErrorKind::Empty, 0));
+        assert_eq!(
+            int_error(parse_int::<i32>(b"12a")),
+            (IntErrorKind::InvalidDigit, 2)
+        );
+    }
+
+    #[test]
+    fn parse_int_overflow() {
+        assert_eq!(parse_int::<u8>(b"255"), Ok(255));
+        assert_eq!(parse_int::<i8>(b"-128"), Ok(-128));
+        assert_eq!(
+            int_error(parse_int::<u8>(b"256")),
+            (IntErrorKind::PosOverflow, 2)
+        );
+        assert_eq!(
+            int_error(parse_int::<i8>(b"-129")),
+            (IntErrorKind::NegOverflow, 3)
+        );
+        assert_eq!(parse_int::<u64>(b"18446744073709551615"), Ok(u64::MAX));
+    }
+
+    #[test]
+    fn parse_int_radix_only_digits() {
+        assert_eq!(parse_int_radix::<u32>(b"70c71", 16), Ok(0x70c71));
+        assert_eq!(
+            parse_int_radix::<u32>(b"FfZ", 36),
+            Ok(15 * 36 * 36 + 15 * 36 + 35)
+        );
+        assert_eq!(
+            int_error(parse_int_radix::<u32>(b"12", 2)),
+            (IntErrorKind::InvalidDigit, 1)
+        );
+    }
+
+    #[test]
+    fn parse_int_radix_prefixes() {
+        assert_eq!(parse_int_radix::<u32>(b"0x1f", 16), Ok(31));
+        assert_eq!(parse_int_radix::<u32>(b"+0X1F", 16), Ok(31));
+        assert_eq!(parse_int_radix::<i32>(b"-0b101", 2), Ok(-5));
+        assert_eq!(parse_int_radix::<i32>(b"0o17", 8), Ok(15));
+        // The prefix has to match the base, and in base 36 `x` is a digit
+        assert_eq!(
+            int_error(parse_int_radix::<u32>(b"0x1", 10)),
+            (IntErrorKind::InvalidDigit, 1)
+        );
+        assert_eq!(parse_int_radix::<u32>(b"0x", 36), Ok(33));
+        assert_eq!(
+            int_error(parse_int_radix::<u32>(b"-0x1", 16)),
+            (IntErrorKind::InvalidDigit, 0)
+        );
+        assert_eq!(
+            int_error(parse_int_radix::<u32>(b"0x", 16)),
+            (IntErrorKind::Empty, 2)
+        );
+        assert_eq!(
+            int_error(parse_int_radix::<u32>(b" 0x1f", 16)),
+            (IntErrorKind::InvalidDigit, 0)
+        );
+        assert_eq!(
+            int_error(parse_int_radix::<u32>(b"0x1f ", 16)),
+            (IntErrorKind::InvalidDigit, 4)
+        );
+    }
+
+    #[test]
+    fn pos_operators() {
+        let a = Pos::new(2, -3);
+        let b = Pos::new(-1, 5);
+        assert_eq!(a + b, Pos::new(1, 2));
+        assert_eq!(a - b, Pos::new(3, -8));
+        assert_eq!(-a, Pos::new(-2, 3));
+        assert_eq!(a * 3, Pos::new(6, -9));
+        assert_eq!(a * -1, -a);
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+        assert_eq!(a + Dir::N, Pos::new(1, -3));
+        assert_eq!(a + Dir::W * 4, Pos::new(2, -7));
+        assert_eq!(a + Dir::S * 0, a);
+        assert_eq!(a + Dir8::SW, Pos::new(3, -4));
+        assert_eq!(Pos::new(-5, 0).signum(), Pos::new(-1, 0));
+        assert_eq!((b - a).signum(), Pos::new(-1, 1));
+        // Reading order, and column major order
+        assert!(Pos::new(0, 5) < Pos::new(1, 0));
+        assert_eq!(
+            Pos::new(0, 5).cmp_column_major(&Pos::new(1, 0)),
+            Ordering::Greater
+        );
+    }
+
+    #[test]
+    fn pos_distances() {
+        let origin = Pos::new(0, 0);
+        let pos = Pos::new(3, -4);
+        assert_eq!(origin.manhattan_distance(pos), 7);
+        assert_eq!(origin.chebyshev_distance(pos), 4);
+        assert_eq!(origin.eucledian_distance(pos), 5);
+        assert_eq!(pos.manhattan_distance(origin), 7);
+        assert_eq!(pos.chebyshev_distance(origin), 4);
+        // Rounded down
+        assert_eq!(origin.eucledian_distance(Pos::new(2, 3)), 3);
+        assert_eq!(pos.manhattan_distance(pos), 0);
+        assert_eq!(pos.chebyshev_distance(pos), 0);
+        assert_eq!(pos.eucledian_distance(pos), 0);
+        for dir in Dir8::ALL {
+            assert_eq!(origin.chebyshev_distance(origin + dir), 1);
+            let manhattan = if dir.is_diagonal() { 2 } else { 1 };
+            assert_eq!(origin.manhattan_distance(origin + dir), manhattan);
+        }
+    }
+
+    #[test]
+    fn dir8_turns() {
+        for dir in Dir8::ALL {
+            let mut turned = dir;
+            for _ in 0..8 {
+                turned = turned.turn_cw();
+            }
+            assert_eq!(turned, dir);
+            assert_eq!(dir.turn_cw().turn_ccw(), dir);
+            assert_eq!(dir.reverse().reverse(), dir);
+            assert_eq!(dir.reverse().offset(), -dir.offset());
+            assert_eq!(dir.turn_cw().turn_cw(), dir.turn_ccw().turn_ccw().reverse());
+        }
+        // A step in each direction, turning 45 degrees between them, walks back to the start
+        let start = Pos::new(3, 7);
+        let mut pos = start;
+        let mut dir = Dir8::E;
+        for _ in 0..8 {
+            pos = pos + dir;
+            dir = dir.turn_ccw();
+        }
+        assert_eq!(pos, start);
+        assert_eq!(dir, Dir8::E);
+        for dir in Dir::ALL {
+            assert_eq!(start + Dir8::from(dir), start + dir);
+            assert_eq!(
+                Dir8::from(dir.turn_cw()),
+                Dir8::from(dir).turn_cw().turn_cw()
+            );
+            assert!(!Dir8::from(dir).is_diagonal());
+        }
+    }
+
+    #[test]
+    fn coordinate_parse_with_spaces() {
+        let line = b"19, 13, 30 @ -2,  1, -2";
+        let (pos, vel) = line.split_once_str("@").unwrap();
+        let pos = Coordinate::<i64>::try_from(pos).unwrap();
+        let vel = Coordinate::<i64>::try_from(vel).unwrap();
+        assert_eq!(pos, Coordinate::new(19, 13, 30));
+        assert_eq!(vel, Coordinate::new(-2, 1, -2));
+        let small = Coordinate2::<u8>::try_from(&b"3,4"[..]).unwrap();
+        assert_eq!(small, Coordinate2::new(3, 4));
+        // The error offsets point into the whole text
+        let err = Coordinate::<i64>::try_from(&b"1, 2x, 3"[..]).unwrap_err();
+        assert!(matches!(
+            err,
+            CommonParseError::InvalidInteger2(ParseIntError2 { offset: 4, .. })
+        ));
+        let err = Coordinate::<i64>::try_from(&b"1, 2"[..]).unwrap_err();
+        assert!(matches!(err, CommonParseError::ExpectedChar(',')));
+        let err = Coordinate2::<i64>::try_from(&b"1, 2, 3"[..]).unwrap_err();
+        assert_eq!(err.offset(), Some(4));
+    }
+
+    #[test]
+    fn coordinate_products() {
+        let unit_x = Coordinate::new(1, 0, 0);
+        let unit_y = Coordinate::new(0, 1, 0);
+        let unit_z = Coordinate::new(0, 0, 1);
+        assert_eq!(unit_x.cross(unit_y), unit_z);
+        assert_eq!(unit_y.cross(unit_z), unit_x);
+        assert_eq!(unit_y.cross(unit_x), -unit_z);
+        let a = Coordinate::new(2, -3, 4);
+        let b = Coordinate::new(5, 6, -7);
+        assert_eq!(a.dot(b), 10 - 18 - 28);
+        assert_eq!(a.cross(b).dot(a), 0);
+        assert_eq!(a.cross(b).dot(b), 0);
+        assert_eq!(a.cross(a), Coordinate::new(0, 0, 0));
+        assert_eq!(a.xy(), Coordinate2::new(2, -3));
+        assert_eq!(Coordinate2::new(1, 0).cross(Coordinate2::new(0, 1)), 1);
+        assert_eq!(Coordinate2::new(0, 1).cross(Coordinate2::new(1, 0)), -1);
+        assert_eq!(Coordinate2::new(3, 4).dot(Coordinate2::new(-4, 3)), 0);
+    }
+
+    #[test]
+    fn coordinate_operators() {
+        let a = Coordinate::new(2, -3, 4);
+        let b = Coordinate::new(5, 6, -7);
+        assert_eq!(a + b, Coordinate::new(7, 3, -3));
+        assert_eq!(a - b, Coordinate::new(-3, -9, 11));
+        assert_eq!(-a, Coordinate::new(-2, 3, -4));
+        assert_eq!(a * 3, Coordinate::new(6, -9, 12));
+        assert_eq!(b / 2, Coordinate::new(2, 3, -3));
+        let mut c = a;
+        c += b;
+        assert_eq!(c, a + b);
+        c -= b;
+        assert_eq!(c, a);
+        c *= -2;
+        assert_eq!(c, Coordinate::new(-4, 6, -8));
+        c /= 2;
+        assert_eq!(c, -a);
+        let mut d = Coordinate2::new(1, 2);
+        d += Coordinate2::new(3, 4);
+        d *= 2;
+        assert_eq!(d, Coordinate2::new(8, 12));
+    }
+
+    #[test]
+    fn coordinate_norms_and_fields() {
+        let a = Coordinate::new(2, -7, 4);
+        let b = Coordinate::new(5, 6, -7);
+        assert_eq!(a.manhattan_norm(), 13);
+        assert_eq!(a.max_norm(), 7);
+        assert_eq!(Coordinate2::new(-3, 0).manhattan_norm(), 3);
+        assert_eq!(a.map(|v| v * 10), Coordinate::new(20, -70, 40));
+        assert_eq!(a.map(i64::is_positive), Coordinate::new(true, false, true));
+        assert_eq!(a.zip_with(b, |a, b| a * b), Coordinate::new(10, -42, -28));
+        assert_eq!(a.min_fields(b), Coordinate::new(2, -7, -7));
+        assert_eq!(a.max_fields(b), Coordinate::new(5, 6, 4));
+        assert_eq!(a.to_string(), "2, -7, 4");
+        assert_eq!(Coordinate2::new(-1, 0).to_string(), "-1, 0");
+        assert_eq!(format!("{a:?}"), "(2, -7, 4)");
+    }
+
+    #[test]
+    fn dir_alphabets() {
+        for dir in Dir::ALL {
+            assert_eq!(Dir::from_nesw(dir.to_nesw()), Some(dir));
+            assert_eq!(Dir::from_udlr(dir.to_udlr()), Some(dir));
+            assert_eq!(Dir::from_arrow(dir.to_arrow()), Some(dir));
+        }
+        assert_eq!(Dir::from_udlr(b'N'), None);
+        assert_eq!(Dir::from_arrow(b'V'), None);
+        assert_eq!(Dir::from_nesw(b'^'), None);
+        assert_eq!(Turn::from_lr(b'L'), Some(Turn::Left));
+        assert_eq!(Turn::from_lr(b'R'), Some(Turn::Right));
+        assert_eq!(Turn::from_lr(b'S'), None);
+    }
+
+    #[test]
+    fn grid_lines_endings() {
+        let lf = grid_lines(b"ab\ncd\n").unwrap();
+        assert_eq!(lf, [(0, &b"ab"[..]), (3, &b"cd"[..])]);
+        let crlf = grid_lines(b"ab\r\ncd\r\n").unwrap();
+        assert_eq!(crlf, [(0, &b"ab"[..]), (4, &b"cd"[..])]);
+        let unterminated = grid_lines(b"ab\ncd").unwrap();
+        assert_eq!(unterminated, lf);
+        let single = grid_lines(b"x").unwrap();
+        assert_eq!(single, [(0, &b"x"[..])]);
+    }
+
+    #[test]
+    fn grid_lines_rejects_empty_and_ragged() {
+        assert!(matches!(grid_lines(b""), Err(CommonParseError::EmptyInput)));
+        assert!(matches!(
+            grid_lines(b"\n"),
+            Err(CommonParseError::EmptyLine { line: 1, offset: 0 })
+        ));
+        assert!(matches!(
+            grid_lines(b"ab\n\ncd\n"),
+            Err(CommonParseError::EmptyLine { line: 2, offset: 3 })
+        ));
+        assert!(matches!(
+            grid_lines(b"ab\ncd\n\n"),
+            Err(CommonParseError::EmptyLine { line: 3, offset: 6 })
+        ));
+        // A short line is cut off at its end, a long one at the first extra cell
+        assert!(matches!(
+            grid_lines(b"abc\nde\n"),
+            Err(CommonParseError::RaggedLine {
+                line: 2,
+                expected: 3,
+                found: 2,
+                offset: 6
+            })
+        ));
+        assert!(matches!(
+            grid_lines(b"ab\r\ncde\r\n"),
+            Err(CommonParseError::RaggedLine {
+                line: 2,
+                expected: 2,
+                found: 3,
+                offset: 6
+            })
+        ));
+    }
+
+    /// Grid of the bytes of `rows`
+    fn byte_grid(rows: &[&str]) -> Grid<u8> {
+        Grid::from_vec(rows[0].len(), rows.len(), rows.concat().into_bytes())
+    }
+
+    #[test]
+    fn grid_iter_and_columns() {
+        let grid = byte_grid(&["abc", "def"]);
+        let cells: Vec<_> = grid.iter().map(|(pos, &ch)| (pos, ch)).collect();
+        assert_eq!(cells.len(), 6);
+        assert_eq!(cells[0], (Pos::new(0, 0), b'a'));
+        assert_eq!(cells[2], (Pos::new(0, 2), b'c'));
+        assert_eq!(cells[3], (Pos::new(1, 0), b'd'));
+        assert_eq!(cells[5], (Pos::new(1, 2), b'f'));
+        let rows: Vec<_> = grid.rows().collect();
+        assert_eq!(rows, [b"abc", b"def"]);
+        let columns: Vec<Vec<u8>> = grid.columns().map(|col| col.copied().collect()).collect();
+        assert_eq!(columns, [b"ad", b"be", b"cf"]);
+    }
+
+    #[test]
+    fn grid_neighbours_at_corners_and_edges() {
+        let grid = byte_grid(&["abc", "def", "ghi"]);
+        let n4 = |row, col| grid.neighbours4(Pos::new(row, col)).collect::<Vec<_>>();
+        let n8 = |row, col| grid.neighbours8(Pos::new(row, col)).count();
+        assert_eq!(n4(0, 0), [Pos::new(0, 1), Pos::new(1, 0)]);
+        assert_eq!(n4(2, 2), [Pos::new(1, 2), Pos::new(2, 1)]);
+        assert_eq!(n4(0, 1), [Pos::new(0, 2), Pos::new(1, 1), Pos::new(0, 0)]);
+        assert_eq!(n4(1, 0), [Pos::new(0, 0), Pos::new(1, 1), Pos::new(2, 0)]);
+        assert_eq!(n4(1, 1).len(), 4);
+        assert_eq!((n8(0, 0), n8(0, 2), n8(2, 0), n8(2, 2)), (3, 3, 3, 3));
+        assert_eq!((n8(0, 1), n8(1, 0), n8(1, 2), n8(2, 1)), (5, 5, 5, 5));
+        assert_eq!(n8(1, 1), 8);
+        assert_eq!(
+            grid.neighbours8(Pos::new(0, 0)).collect::<Vec<_>>(),
+            [Pos::new(0, 1), Pos::new(1, 1), Pos::new(1, 0)]
+        );
+
+        // Nothing outside the grid is a neighbour, not even of an outside position
+        let single = byte_grid(&["x"]);
+        assert_eq!(single.neighbours8(Pos::new(0, 0)).count(), 0);
+        assert_eq!(
+            single.neighbours4(Pos::new(-1, 0)).collect::<Vec<_>>(),
+            [Pos::new(0, 0)]
+        );
+    }
+
+    #[test]
+    fn grid_find_all_and_position() {
+        let grid = byte_grid(&["a#b", "##a", "b.a"]);
+        let walls: Vec<_> = grid.find_all(|ch| ch == b'#').collect();
+        assert_eq!(walls, [Pos::new(0, 1), Pos::new(1, 0), Pos::new(1, 1)]);
+        let a: Vec<_> = grid.find_all(|ch| ch == b'a').collect();
+        assert_eq!(a, [Pos::new(0, 0), Pos::new(1, 2), Pos::new(2, 2)]);
+        assert_eq!(grid.find_all(|ch| ch == b'z').count(), 0);
+        assert_eq!(grid.position(|ch| ch == b'b'), Some(Pos::new(0, 2)));
+        assert_eq!(grid.position(|ch| ch == b'z'), None);
+        assert_eq!(grid.count_if(|ch| ch == b'a'), 3);
+    }
+
+    #[test]
+    fn grid_components_skip_impassable() {
+        let grid = byte_grid(&["ab#a", "a##a", "#bba"]);
+        let (labels, count) = grid.components(|_, &ch| ch != b'#', |a, b| grid[a] == grid[b]);
+        assert_eq!(count, 4);
+        let labels: Vec<_> = labels.iter().map(|(_, &label)| label).collect();
+        #[rustfmt::skip]
+        assert_eq!(labels, [
+            Some(0), Some(1), None, Some(2),
+            Some(0), None, None, Some(2),
+            None, Some(3), Some(3), Some(2),
+        ]);
+    }
+
+    #[test]
+    fn grid_bfs_distances_and_path() {
+        let grid = byte_grid(&["...", "##.", "..."]);
+        let map = grid.bfs([Pos::new(0, 0)], |_, to| grid[to] == b'.');
+        assert_eq!(map.distance(Pos::new(2, 0)), Some(6));
+        assert_eq!(map.distance(Pos::new(1, 0)), None);
+        let path = map.path_to(Pos::new(1, 2)).unwrap();
+        assert_eq!(
+            path,
+            [
+                Pos::new(0, 0),
+                Pos::new(0, 1),
+                Pos::new(0, 2),
+                Pos::new(1, 2)
+            ]
+        );
+    }
+
+    #[test]
+    fn grid_rotations_and_flips() {
+        let grid = byte_grid(&["abc", "def"]);
+        assert_eq!(grid.rotate_cw().to_string(), "da\neb\nfc\n");
+        assert_eq!(grid.rotate_ccw().to_string(), "cf\nbe\nad\n");
+        assert_eq!(grid.rotate_180().to_string(), "fed\ncba\n");
+        assert_eq!(grid.transpose().to_string(), "ad\nbe\ncf\n");
+        assert_eq!(grid.flip_horizontal().to_string(), "cba\nfed\n");
+        assert_eq!(grid.flip_vertical().to_string(), "def\nabc\n");
+    }
+
+    #[test]
+    fn grid_transforms_compose() {
+        let grid = byte_grid(&["abc", "def"]);
+        let text = grid.to_string();
+        let cw = grid.rotate_cw();
+        assert_eq!((cw.width(), cw.height()), (2, 3));
+        assert_eq!(cw.rotate_cw().rotate_cw().rotate_cw().to_string(), text);
+        assert_eq!(cw.rotate_ccw().to_string(), text);
+        assert_eq!(grid.transpose().transpose().to_string(), text);
+        assert_eq!(cw.rotate_cw().to_string(), grid.rotate_180().to_string());
+        assert_eq!(
+            grid.flip_horizontal().flip_vertical().to_string(),
+            grid.rotate_180().to_string()
+        );
+        assert_eq!(
+            grid.transpose().flip_horizontal().to_string(),
+            cw.to_string()
+        );
+    }
+
+    #[test]
+    fn grid_views() {
+        let grid = byte_grid(&["abcd", "efgh", "ijkl"]);
+        let view = grid.view(Pos::new(1, 1), 2, 2).unwrap();
+        assert_eq!(view.to_grid().to_string(), "fg\njk\n");
+        assert_eq!(view.get(Pos::new(1, 0)), Some(&b'j'));
+        assert_eq!(view.get(Pos::new(0, 2)), None);
+        assert_eq!(view.get(Pos::new(-1, 0)), None);
+        let cells: Vec<_> = view.iter().map(|(pos, &ch)| (pos, ch)).collect();
+        assert_eq!(cells[3], (Pos::new(1, 1), b'k'));
+
+        assert!(grid.view(Pos::new(0, 0), 4, 3).is_some());
+        assert!(grid.view(Pos::new(2, 1), 2, 2).is_none());
+        assert!(grid.view(Pos::new(0, 3), 2, 1).is_none());
+        assert!(grid.view(Pos::new(-1, 0), 1, 1).is_none());
+        assert_eq!(grid.as_view().to_grid().to_string(), grid.to_string());
+    }
+
+    #[test]
+    fn grid_windows_at_the_edges() {
+        let grid = byte_grid(&["abcd", "efgh", "ijkl"]);
+        let origins: Vec<_> = grid.windows(2, 2).map(|view| view.origin()).collect();
+        assert_eq!(origins.len(), 6);
+        assert_eq!(origins[0], Pos::new(0, 0));
+        assert_eq!(origins[2], Pos::new(0, 2));
+        assert_eq!(origins[5], Pos::new(1, 2));
+        let last = grid.windows(2, 2).last().unwrap();
+        assert_eq!(last.to_grid().to_string(), "gh\nkl\n");
+        assert_eq!(grid.windows(1, 1).count(), 12);
+        assert_eq!(grid.windows(4, 3).count(), 1);
+        assert_eq!(grid.windows(4, 1).count(), 3);
+        assert_eq!(grid.windows(5, 1).count(), 0);
+        assert_eq!(grid.windows(1, 4).count(), 0);
+    }
+
+    /// Cell type of the rendering tests, parsed from and shown as `.` and `#`
+    #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+    enum Cell {
+        #[default]
+        Open,
+        Wall,
+    }
+
+    impl TryFrom<u8> for Cell {
+        type Error = CommonParseError;
+
+        fn try_from(ch: u8) -> Result<Self, Self::Error> {
+            match ch {
+                b'.' => Ok(Self::Open),
+                b'#' => Ok(Self::Wall),
+                ch => Err(CommonParseError::ExpectedChar(char::from(ch))),
+            }
+        }
+    }
+
+    impl CellByte for Cell {
+        fn to_byte(self) -> u8 {
+            match self {
+                Self::Open => b'.',
+                Self::Wall => b'#',
+            }
+        }
+    }
+
+    #[test]
+    fn grid_display_round_trips() {
+        let text = "#..#\n.##.\n...#\n";
+        let grid: Grid<Cell> = text.parse().unwrap();
+        assert_eq!(grid[Pos::new(2, 3)], Cell::Wall);
+        assert_eq!(grid.to_string(), text);
+        let reparsed: Grid<Cell> = grid.to_string().parse().unwrap();
+        assert_eq!(reparsed.to_string(), text);
+        // CRLF and a missing final newline render with LF after every row
+        let grid: Grid<Cell> = "#.\r\n.#".parse().unwrap();
+        assert_eq!(grid.to_string(), "#.\n.#\n");
+    }
+
+    #[test]
+    fn grid_overlay_marks_positions() {
+        let grid: Grid<Cell> = "#..#\n.##.\n...#\n".parse().unwrap();
+        assert_eq!(grid.overlay().to_string(), grid.to_string());
+        let path = [
+            Pos::new(0, 1),
+            Pos::new(0, 2),
+            Pos::new(1, 3),
+            Pos::new(2, 2),
+        ];
+        let overlay = grid
+            .overlay()
+            .highlight(path, 'O')
+            .highlight([Pos::new(2, 2), Pos::new(5, 5), Pos::new(-1, 0)], 'S');
+        assert_eq!(overlay.to_string(), "#OO#\n.##O\n..S#\n");
+    }
+
+    #[test]
+    fn repeating_grid_split_and_join() {
+        let grid = byte_grid(&["abc", "def"]);
+        let repeating = RepeatingGrid::new(&grid);
+        assert_eq!(
+            repeating.split(Pos::new(1, 2)),
+            (Pos::new(0, 0), Pos::new(1, 2))
+        );
+        assert_eq!(
+            repeating.split(Pos::new(5, 7)),
+            (Pos::new(2, 2), Pos::new(1, 1))
+        );
+        // Negative positions are in negative tiles, with the local position still in the tile
+        assert_eq!(
+            repeating.split(Pos::new(-1, -1)),
+            (Pos::new(-1, -1), Pos::new(1, 2))
+        );
+        assert_eq!(
+            repeating.split(Pos::new(-2, -3)),
+            (Pos::new(-1, -1), Pos::new(0, 0))
+        );
+        assert_eq!(
+            repeating.split(Pos::new(-3, -4)),
+            (Pos::new(-2, -2), Pos::new(1, 2))
+        );
+        for row in -5..5 {
+            for col in -7..7 {
+                let pos = Pos::new(row, col);
+                let (tile, local) = repeating.split(pos);
+                assert_eq!(repeating.join(tile, local), pos);
+                assert_eq!(repeating.tile(pos), tile);
+                assert_eq!(repeating[pos], grid[local]);
             }
-            _ => return Err(ParseIntError2::new(IntErrorKind::InvalidDigit)),
         }
+        assert_eq!(repeating[Pos::new(-1, -1)], b'f');
+        assert_eq!(
+            repeating.join(Pos::new(-1, 2), Pos::new(0, 1)),
+            Pos::new(-2, 7)
+        );
+    }
+
+    #[test]
+    fn repeating_grid_count_tiles() {
+        let grid = byte_grid(&["abc", "def"]);
+        let repeating = RepeatingGrid::new(&grid);
+        assert_eq!(repeating.count_tiles([]), 0);
+        assert_eq!(repeating.count_tiles([Pos::new(0, 0), Pos::new(1, 2)]), 1);
+        let positions = [
+            Pos::new(0, 0),
+            Pos::new(0, -1),
+            Pos::new(-1, 0),
+            Pos::new(-1, -1),
+            Pos::new(-2, -3),
+            Pos::new(2, 3),
+        ];
+        assert_eq!(repeating.count_tiles(positions), 5);
+    }
+
+    #[test]
+    fn scanner_pieces() {
+        let mut scanner = Scanner::new(b"move -12 to x_1: 3,4,5 end");
+        scanner.literal("move ").unwrap();
+        assert_eq!(scanner.int::<i32>().unwrap(), -12);
+        scanner.literal(" to ").unwrap();
+        assert_eq!(scanner.ident().unwrap(), b"x_1");
+        assert_eq!(scanner.byte().unwrap(), b':');
+        scanner.literal(" ").unwrap();
+        assert_eq!(scanner.ints::<u8>(",").unwrap(), [3, 4, 5]);
+        assert_eq!(scanner.until("d").unwrap(), b" en");
+        assert_eq!(scanner.rest(), b"d");
+        assert!(matches!(
+            scanner.end(),
+            Err(CommonParseError::TrailingText { offset: 25 })
+        ));
+        assert_eq!(scanner.tail(), b"d");
+        scanner.end().unwrap();
+    }
+
+    #[test]
+    fn scanner_error_offsets() {
+        let mut scanner = Scanner::new(b"ab = 1x");
+        scanner.ident().unwrap();
+        assert!(matches!(
+            scanner.literal(" := "),
+            Err(CommonParseError::ExpectedText { offset: 3, .. })
+        ));
+        scanner.literal(" = ").unwrap();
+        let err = scanner.int::<u8>().and_then(|_| scanner.end()).unwrap_err();
+        assert!(matches!(err, CommonParseError::TrailingText { offset: 6 }));
+        let err = Scanner::new(b"+1").int::<i32>().unwrap_err();
+        assert_eq!(err.offset(), None);
+        assert!(matches!(
+            err,
+            CommonParseError::InvalidInteger2(ParseIntError2 { offset: 0, .. })
+        ));
+        assert!(matches!(
+            Scanner::new(b"").byte(),
+            Err(CommonParseError::UnexpectedEnd { offset: 0 })
+        ));
+        assert!(matches!(
+            Scanner::new(b"abc").until(";"),
+            Err(CommonParseError::ExpectedText { offset: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn scan_doc_examples() {
+        let (name, left, right) =
+            scan!(b"AAA = (BBB, CCC)" => ident " = (" ident ", " ident ")").unwrap();
+        assert_eq!((name, left, right), (&b"AAA"[..], &b"BBB"[..], &b"CCC"[..]));
+        let (name, rules) = scan!(b"px{a<2006:qkq,rfg}" => ident "{" until("}") "}").unwrap();
+        assert_eq!((name, rules), (&b"px"[..], &b"a<2006:qkq,rfg"[..]));
+    }
+
+    #[test]
+    fn scan_locates_errors() {
+        let result: Result<(u8, u8), _> = scan!(b"x=1, y=2!" => "x=" int ", y=" int);
+        assert!(matches!(
+            result,
+            Err(CommonParseError::TrailingText { offset: 8 })
+        ));
+        let result: Result<(u8, u8), _> = scan!(b"x=1, z=2" => "x=" int ", y=" int);
+        assert!(matches!(
+            result,
+            Err(CommonParseError::ExpectedText { offset: 5, .. })
+        ));
+        let result: Result<(u8, u8), _> = scan!(b"x=1, y=" => "x=" int ", y=" int);
+        let err = result.unwrap_err();
+        assert!(matches!(
+            err,
+            CommonParseError::InvalidInteger2(ParseIntError2 { offset: 7, .. })
+        ));
+    }
+
+    #[test]
+    fn cloned_errors_keep_their_source() {
+        let err = CommonParseError::invalid_part(CommonParseError::TrailingText { offset: 2 }, 10);
+        let err = err.clone();
+        assert_eq!(err.offset(), Some(10));
+        let source = std::error::Error::source(&err).and_then(std::error::Error::source);
+        let source = source.and_then(|source| source.downcast_ref::<CommonParseError>());
+        assert_eq!(source.and_then(CommonParseError::offset), Some(2));
+    }
+
+    #[test]
+    fn cycle_find_and_nth() {
+        // 0, 1, 2, then 3, 4, 5, 6 over and over
+        let cycle = Cycle::find(0_u32, |n| *n = if *n < 6 { *n + 1 } else { 3 });
+        assert_eq!(cycle.prefix_len(), 3);
+        assert_eq!(cycle.period(), 4);
+        assert_eq!(*cycle.nth(0), 0);
+        assert_eq!(*cycle.nth(2), 2);
+        assert_eq!(*cycle.nth(6), 6);
+        assert_eq!(*cycle.nth(7), 3);
+        assert_eq!(*cycle.nth(3 + 4 * 1000 + 2), 5);
+    }
+
+    #[test]
+    fn cycle_without_prefix() {
+        let cycle = Cycle::find(5_u8, |n| *n = (*n + 1) % 6);
+        assert_eq!((cycle.prefix_len(), cycle.period()), (0, 6));
+        assert_eq!(*cycle.nth(1_000_000_000), 3);
+        let cycle = Cycle::find((), |()| ());
+        assert_eq!((cycle.prefix_len(), cycle.period()), (0, 1));
+    }
+
+    #[test]
+    fn cycle_find_by_key() {
+        // The step count changes every step, but doesn't affect the position
+        let cycle = Cycle::find_by_key(
+            (0_u8, 0_usize),
+            |(pos, steps)| {
+                *pos = (*pos + 2) % 5;
+                *steps += 1;
+            },
+            |&(pos, _)| pos,
+        );
+        assert_eq!((cycle.prefix_len(), cycle.period()), (0, 5));
+        assert_eq!(*cycle.nth(12), (4, 2));
+    }
+
+    #[test]
+    fn sparse_grid_bounds_follow_removals() {
+        let mut grid: SparseGrid<u8> = [(Pos::new(-2, 3), 1), (Pos::new(4, -1), 2)]
+            .into_iter()
+            .collect();
+        grid.insert(Pos::new(4, 5), 3);
+        grid.insert(Pos::new(4, 5), 4);
+        assert_eq!(grid.bounds(), Some((Pos::new(-2, -1), Pos::new(4, 5))));
+
+        // Another cell is still on the bottom edge
+        assert_eq!(grid.remove(Pos::new(4, 5)), Some(4));
+        assert_eq!(grid.bounds(), Some((Pos::new(-2, -1), Pos::new(4, 3))));
+        assert_eq!(grid.remove(Pos::new(4, 5)), None);
+        assert_eq!(grid.remove(Pos::new(4, -1)), Some(2));
+        assert_eq!(grid.bounds(), Some((Pos::new(-2, 3), Pos::new(-2, 3))));
+        assert_eq!(grid.remove(Pos::new(-2, 3)), Some(1));
+        assert_eq!(grid.bounds(), None);
+        assert!(grid.is_empty());
     }
 }
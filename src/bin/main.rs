@@ -1,7 +1,58 @@
 use std::env;
+use std::path::Path;
+use std::process::ExitCode;
 
-pub fn main() {
-    let day = env::args().nth(1).and_then(|s| s.parse::<usize>().ok());
-    let repeat = env::args().nth(2).and_then(|s| s.parse::<usize>().ok());
-    aoc_rust_2023::run(day, repeat);
+use aoc_rust_2023::cli::{parse_args, Command, USAGE};
+use aoc_rust_2023::{scaffold, DAYS};
+
+pub fn main() -> ExitCode {
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(Command::Run(options)) => options,
+        Ok(Command::List) => {
+            for solution in DAYS {
+                let names: Vec<_> = solution
+                    .inputs()
+                    .into_iter()
+                    .map(|(name, _)| name)
+                    .collect();
+                println!("Day {:02}: {}", solution.day(), names.join(", "));
+            }
+            return ExitCode::SUCCESS;
+        }
+        Ok(Command::New(day)) => {
+            return match scaffold::new_day(Path::new("."), day) {
+                Ok(files) => {
+                    for file in files {
+                        println!("Wrote {}", file.display());
+                    }
+                    ExitCode::SUCCESS
+                }
+                Err(err) => {
+                    eprintln!("Error: {err}");
+                    ExitCode::FAILURE
+                }
+            };
+        }
+        Ok(Command::Help) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("Error: {err}");
+            eprintln!("Run 'main --help' for usage");
+            return ExitCode::from(2);
+        }
+    };
+    match aoc_rust_2023::run(&options) {
+        Ok(summary) if summary.invalid > 0 => {
+            eprintln!("Error: {} input(s) could not be parsed", summary.invalid);
+            ExitCode::from(3)
+        }
+        Ok(summary) if summary.is_success() => ExitCode::SUCCESS,
+        Ok(_) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("Error: {err}");
+            ExitCode::from(3)
+        }
+    }
 }
@@ -0,0 +1,395 @@
+use std::num::NonZeroUsize;
+use std::thread;
+
+use thiserror::Error;
+
+use crate::get_day;
+use crate::report::OutputFormat;
+use crate::runner::{InputFilter, InputSource, RunOptions};
+use crate::solution::Parts;
+
+pub const USAGE: &str = "\
+Usage: main [run] [OPTIONS] [DAYS]
+       main list
+       main new <DAY>
+       main help
+
+Commands:
+  run                  Solve the selected days (default)
+  list                 List the implemented days and their embedded inputs
+  new <DAY>            Create a new day from the src/dayXX template, and register it
+                       in lib.rs, the crate error type and Cargo.toml. Run from the
+                       root of the repository.
+  help                 Print this help
+
+Arguments:
+  [DAYS]               Days to run, as a list of days and ranges, like `1-5,12`.
+                       Defaults to all days.
+
+Options:
+  -p, --part <1|2>     Only solve one part
+  -e, --examples       Only solve the embedded examples
+  -r, --real           Only solve the embedded real input
+  -i, --input <PATH>   Read the input from a file, a directory of `dayNN.txt` files,
+                       or `-` for standard input
+  -n, --repeat <N>     Solve each input N times, and print timing statistics.
+                       Only the real input is used, unless --examples is given.
+  -f, --format <FMT>   Output format: text (default), json or csv. The machine
+                       readable formats have one record per day, input and part.
+  -j, --jobs <N>       Solve up to N days in parallel, still printed in day order.
+                       0 uses one thread per CPU. Defaults to 1, so the timings
+                       are not disturbed.
+  -h, --help           Print this help
+
+Exit status:
+  0                    All answers are correct, or unchecked
+  1                    Some answers did not match answers.toml
+  2                    Invalid command-line arguments
+  3                    An input could not be read, or could not be parsed
+  101                  A solution panicked";
+
+#[derive(Debug, Error)]
+pub enum ArgsError {
+    #[error("Unknown option '{0}'")]
+    UnknownOption(String),
+    #[error("Missing value for '{0}'")]
+    MissingValue(String),
+    #[error("Invalid value '{value}' for '{option}': {reason}")]
+    InvalidValue {
+        option: String,
+        value: String,
+        reason: &'static str,
+    },
+    #[error("Invalid day '{0}': expected a day from 1 to 25, or a range like 1-5")]
+    InvalidDay(String),
+    #[error("Day {0} is not implemented")]
+    MissingDay(u8),
+    #[error("Unexpected argument '{0}'")]
+    UnexpectedArgument(String),
+    #[error("Options '{0}' and '{1}' can not be used together")]
+    Conflict(&'static str, &'static str),
+    #[error("A single input file can only be used when running one day")]
+    SingleInput,
+}
+
+impl ArgsError {
+    fn invalid_value(option: String, value: String, reason: &'static str) -> Self {
+        Self::InvalidValue {
+            option,
+            value,
+            reason,
+        }
+    }
+}
+
+/// What to do, as selected on the command line
+#[derive(Debug)]
+pub enum Command {
+    Run(RunOptions),
+    List,
+    New(u8),
+    Help,
+}
+
+/// Parse the command-line arguments, without the program name.
+///
+/// Options may be given more than once, and the last value is used.
+///
+/// # Errors
+///
+/// Returns an error for unknown options, missing or invalid values, and options that can not be
+/// used together.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Command, ArgsError> {
+    let mut args = args.into_iter().peekable();
+    match args.peek().map(String::as_str) {
+        Some("run") => {
+            args.next();
+        }
+        Some("list") => {
+            args.next();
+            return match args.next() {
+                Some(arg) => Err(ArgsError::UnexpectedArgument(arg)),
+                None => Ok(Command::List),
+            };
+        }
+        Some("new") => {
+            args.next();
+            let day = args
+                .next()
+                .ok_or(ArgsError::MissingValue("new".to_string()))?;
+            let Ok(day @ 1..=25) = day.parse::<u8>() else {
+                return Err(ArgsError::InvalidDay(day));
+            };
+            return match args.next() {
+                Some(arg) => Err(ArgsError::UnexpectedArgument(arg)),
+                None => Ok(Command::New(day)),
+            };
+        }
+        Some("help") => return Ok(Command::Help),
+        _ => {}
+    }
+    parse_run_options(args)
+}
+
+/// Parse the options of the `run` command. `--help` anywhere prints the help instead.
+fn parse_run_options(mut args: impl Iterator<Item = String>) -> Result<Command, ArgsError> {
+    let mut options = RunOptions::default();
+    let mut days = None;
+    let mut examples = false;
+    let mut real = false;
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                (flag.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| ArgsError::MissingValue(flag.clone()))
+        };
+        match flag.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "-p" | "--part" => {
+                let value = value()?;
+                options.parts = match value.as_str() {
+                    "1" => Parts {
+                        part_1: true,
+                        part_2: false,
+                    },
+                    "2" => Parts {
+                        part_1: false,
+                        part_2: true,
+                    },
+                    _ => return Err(ArgsError::invalid_value(flag, value, "expected 1 or 2")),
+                };
+            }
+            "-e" | "--examples" => examples = true,
+            "-r" | "--real" => real = true,
+            "-i" | "--input" => {
+                let value = value()?;
+                options.source = if value == "-" {
+                    InputSource::Stdin
+                } else {
+                    InputSource::Path(value.into())
+                };
+            }
+            "-n" | "--repeat" => {
+                let value = value()?;
+                match value.parse::<usize>() {
+                    Ok(count) if count > 0 => options.repeat = Some(count),
+                    _ => {
+                        return Err(ArgsError::invalid_value(
+                            flag,
+                            value,
+                            "expected a positive number",
+                        ))
+                    }
+                }
+            }
+            "-j" | "--jobs" => {
+                let value = value()?;
+                options.jobs = match value.parse::<usize>() {
+                    Ok(0) => thread::available_parallelism().map_or(1, NonZeroUsize::get),
+                    Ok(jobs) => jobs,
+                    Err(_) => {
+                        return Err(ArgsError::invalid_value(flag, value, "expected a number"))
+                    }
+                };
+            }
+            "-f" | "--format" => {
+                let value = value()?;
+                options.format = match value.as_str() {
+                    "text" => OutputFormat::Text,
+                    "json" => OutputFormat::Json,
+                    "csv" => OutputFormat::Csv,
+                    _ => {
+                        return Err(ArgsError::invalid_value(
+                            flag,
+                            value,
+                            "expected text, json or csv",
+                        ))
+                    }
+                };
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(ArgsError::UnknownOption(arg))
+            }
+            _ if days.is_none() => days = Some(parse_days(&arg)?),
+            _ => return Err(ArgsError::UnexpectedArgument(arg)),
+        }
+    }
+    if let Some(days) = days {
+        options.days = days;
+    }
+    check_run_options(options, examples, real)
+}
+
+/// Select the embedded inputs, and check that the options can be used together
+fn check_run_options(
+    mut options: RunOptions,
+    examples: bool,
+    real: bool,
+) -> Result<Command, ArgsError> {
+    options.inputs = match (examples, real) {
+        (true, true) => return Err(ArgsError::Conflict("--examples", "--real")),
+        (true, false) => InputFilter::Examples,
+        (false, true) => InputFilter::Input,
+        (false, false) if options.repeat.is_some() => InputFilter::Input,
+        (false, false) => InputFilter::All,
+    };
+    if (examples || real) && !matches!(options.source, InputSource::Embedded) {
+        return Err(ArgsError::Conflict(
+            if examples { "--examples" } else { "--real" },
+            "--input",
+        ));
+    }
+    if options.days.len() != 1 && options.source.is_single() {
+        return Err(ArgsError::SingleInput);
+    }
+    Ok(Command::Run(options))
+}
+
+/// Parse a list of days and ranges, like `1-5,12`, into a sorted list without duplicates
+///
+/// # Errors
+///
+/// Returns an error if a day is out of range or not implemented, or a range is reversed.
+pub fn parse_days(text: &str) -> Result<Vec<u8>, ArgsError> {
+    let parse_day = |s: &str| {
+        if let Ok(day @ 1..=25) = s.trim().parse::<u8>() {
+            Ok(day)
+        } else {
+            Err(ArgsError::InvalidDay(s.to_string()))
+        }
+    };
+    let mut days = Vec::new();
+    for item in text.split(',') {
+        let (first, last) = if let Some((first, last)) = item.split_once('-') {
+            (parse_day(first)?, parse_day(last)?)
+        } else {
+            let day = parse_day(item)?;
+            (day, day)
+        };
+        if first > last {
+            return Err(ArgsError::InvalidDay(item.to_string()));
+        }
+        days.extend(first..=last);
+    }
+    days.sort_unstable();
+    days.dedup();
+    if let Some(&day) = days.iter().find(|&&day| get_day(day).is_none()) {
+        return Err(ArgsError::MissingDay(day));
+    }
+    Ok(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_options(args: &[&str]) -> Result<RunOptions, ArgsError> {
+        match parse_args(args.iter().map(ToString::to_string))? {
+            Command::Run(options) => Ok(options),
+            command => panic!("expected a run command, got {command:?}"),
+        }
+    }
+
+    #[test]
+    fn days_and_ranges() {
+        assert_eq!(parse_days("1-5,12").unwrap(), [1, 2, 3, 4, 5, 12]);
+        assert_eq!(parse_days("12,3-4,3").unwrap(), [3, 4, 12]);
+        assert_eq!(parse_days("7-7").unwrap(), [7]);
+        assert_eq!(parse_days(" 1 - 2 ,25").unwrap(), [1, 2, 25]);
+        assert_eq!(parse_days("1-25").unwrap().len(), 25);
+    }
+
+    #[test]
+    fn invalid_days() {
+        for text in [
+            "0", "26", "5-3", "0-2", "24-26", "", "1,", "1-", "-1", "x", "1-2-3",
+        ] {
+            assert!(
+                matches!(parse_days(text), Err(ArgsError::InvalidDay(_))),
+                "{text:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn day_argument() {
+        assert_eq!(run_options(&[]).unwrap().days.len(), crate::DAYS.len());
+        assert_eq!(run_options(&["run", "3,1"]).unwrap().days, [1, 3]);
+        assert!(matches!(
+            run_options(&["26"]),
+            Err(ArgsError::InvalidDay(day)) if day == "26"
+        ));
+        assert!(matches!(
+            run_options(&["1", "2"]),
+            Err(ArgsError::UnexpectedArgument(arg)) if arg == "2"
+        ));
+    }
+
+    #[test]
+    fn repeated_options_use_the_last_value() {
+        let options = run_options(&["-p", "1", "--part=2", "-f", "json", "--format", "csv"]);
+        let options = options.unwrap();
+        assert!(!options.parts.part_1 && options.parts.part_2);
+        assert!(matches!(options.format, OutputFormat::Csv));
+        let options = run_options(&["-n", "3", "-n", "5", "-e", "-e"]).unwrap();
+        assert_eq!(options.repeat, Some(5));
+        assert_eq!(options.inputs, InputFilter::Examples);
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert!(matches!(
+            run_options(&["-e", "--real"]),
+            Err(ArgsError::Conflict("--examples", "--real"))
+        ));
+        assert!(matches!(
+            run_options(&["1", "-i", "x.txt", "-r"]),
+            Err(ArgsError::Conflict("--real", "--input"))
+        ));
+        assert!(matches!(
+            run_options(&["1-2", "-i", "-"]),
+            Err(ArgsError::SingleInput)
+        ));
+        assert!(matches!(
+            run_options(&["-p", "3"]),
+            Err(ArgsError::InvalidValue { value, .. }) if value == "3"
+        ));
+        assert!(matches!(
+            run_options(&["-n", "0"]),
+            Err(ArgsError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            run_options(&["--jobs"]),
+            Err(ArgsError::MissingValue(flag)) if flag == "--jobs"
+        ));
+        assert!(matches!(
+            run_options(&["--verbose"]),
+            Err(ArgsError::UnknownOption(_))
+        ));
+    }
+
+    #[test]
+    fn commands() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(ToString::to_string));
+        assert!(matches!(parse(&["list"]), Ok(Command::List)));
+        assert!(matches!(parse(&["help"]), Ok(Command::Help)));
+        assert!(matches!(parse(&["1", "--help"]), Ok(Command::Help)));
+        assert!(matches!(parse(&["new", "7"]), Ok(Command::New(7))));
+        assert!(matches!(
+            parse(&["new", "0"]),
+            Err(ArgsError::InvalidDay(_))
+        ));
+        assert!(matches!(parse(&["new"]), Err(ArgsError::MissingValue(_))));
+        assert!(matches!(
+            parse(&["list", "1"]),
+            Err(ArgsError::UnexpectedArgument(_))
+        ));
+    }
+}
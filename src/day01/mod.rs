@@ -1,35 +1,50 @@
-use bstr::ByteSlice;
+use thiserror::Error;
+
+use crate::aoclib::{lines_with_offsets, CommonParseError};
+use crate::solution::Solution;
 
 const EXAMPLE1: &str = include_str!("example1.txt");
 const EXAMPLE2: &str = include_str!("example2.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 01");
-
-    println!("++Example");
-    let example1 = parse_input(EXAMPLE1);
-    println!("|+-Part 1: {} (expected 142)", part_1(&example1));
-    let example2 = parse_input(EXAMPLE2);
-    println!("|'-Part 2: {} (expected 281)", part_2(&example2));
-
-    println!("++Input");
-    let input = parse_input(INPUT);
-    println!("|+-Part 1: {} (expected 54927)", part_1(&input));
-    println!("|'-Part 2: {} (expected 54581)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Vec<Input<'static>> {
-    parse_input(INPUT)
+    parse_input(INPUT).expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 1;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] = &[
+        ("example1", EXAMPLE1, ()),
+        ("example2", EXAMPLE2, ()),
+        ("input", INPUT, ()),
+    ];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Vec<Input<'a>>;
+    type Transformed<'a> = Vec<Input<'a>>;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        parse_input(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -248,10 +263,29 @@ pub struct Input<'a> {
     line: &'a [u8],
 }
 
-fn parse_input(text: &str) -> Vec<Input> {
+#[derive(Debug, Error)]
+pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
+    #[error("Line has no digit, neither as a number nor spelled out")]
+    MissingDigit,
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
+}
+
+fn parse_input(text: &str) -> Result<Vec<Input<'_>>, ParseInputError> {
     let mut res: Vec<Input> = Vec::with_capacity(1000);
-    for line in text.as_bytes().lines() {
+    for (offset, line) in lines_with_offsets(text.as_bytes()) {
+        if match_forward(line).is_none() {
+            return Err(CommonParseError::invalid_part(
+                ParseInputError::MissingDigit,
+                offset,
+            ));
+        }
         res.push(Input { line });
     }
-    res
+    if res.is_empty() {
+        return Err(ParseInputError::EmptyInput);
+    }
+    Ok(res)
 }
@@ -4,36 +4,47 @@ use std::str::FromStr;
 use smallvec::SmallVec;
 use thiserror::Error;
 
-use crate::aoclib::{ParseIntError2, parse_int};
+use crate::aoclib::{lines_with_offsets, parse_int, CommonParseError, ParseIntError2};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 02");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1: {} (expected 8)", part_1(&example));
-    println!("|'-Part 2: {} (expected 2286)", part_2(&example));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 2176)", part_1(&input));
-    println!("|'-Part 2: {} (expected 63700)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 2;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -108,10 +119,18 @@ impl Round {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
+    #[error("Expected line to start with 'Game '")]
+    MissingPrefix,
     #[error("Expected character: {0:?}")]
     Expected(char),
-    #[error(transparent)]
+    #[error("Unknown color: {0:?}")]
+    InvalidColor(String),
+    #[error("{0}")]
     InvalidNumber(#[from] ParseIntError2),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 impl TryFrom<&[u8]> for Round {
@@ -119,17 +138,28 @@ impl TryFrom<&[u8]> for Round {
 
     fn try_from(piece: &[u8]) -> Result<Self, Self::Error> {
         let mut res = Round::new();
+        let mut offset = 0;
         for cube in piece.split(|&ch| ch == b',') {
+            let start = offset + cube.len() - cube.trim_ascii_start().len();
+            offset += cube.len() + 1;
             let (num_str, color_str) = cube
                 .trim_ascii_start()
                 .split_once(|&ch| ch == b' ')
-                .ok_or(ParseInputError::Expected(' '))?;
-            let num: u8 = parse_int(num_str)?;
-            match color_str[0] {
-                b'r' => res.red += num,
-                b'g' => res.green += num,
-                b'b' => res.blue += num,
-                _ => (),
+                .ok_or_else(|| {
+                    CommonParseError::invalid_part(ParseInputError::Expected(' '), start)
+                })?;
+            let num: u8 = parse_int(num_str).map_err(|err| err.offset_by(start))?;
+            match color_str {
+                b"red" => res.red += num,
+                b"green" => res.green += num,
+                b"blue" => res.blue += num,
+                _ => {
+                    let err = ParseInputError::InvalidColor(color_str.to_str_lossy().into());
+                    return Err(CommonParseError::invalid_part(
+                        err,
+                        start + num_str.len() + 1,
+                    ));
+                }
             };
         }
         Ok(res)
@@ -140,15 +170,22 @@ impl TryFrom<&[u8]> for Game {
     type Error = ParseInputError;
 
     fn try_from(line: &[u8]) -> Result<Self, Self::Error> {
-        let line = &line[5..]; //.strip_prefix("Game ").ok_or(ParseInputError::Expected(...))?;
+        const PREFIX: &[u8] = b"Game ";
+        let line = line
+            .strip_prefix(PREFIX)
+            .ok_or(ParseInputError::MissingPrefix)?;
         let (id_str, line) = line
             .split_once(|&ch| ch == b':')
             .ok_or(ParseInputError::Expected(':'))?;
-        let id: usize = parse_int(id_str)?;
+        let id: usize = parse_int(id_str).map_err(|err| err.offset_by(PREFIX.len()))?;
         let mut rounds = SmallVec::new();
+        let mut offset = PREFIX.len() + id_str.len() + 1;
         for round_str in line.split(|&ch| ch == b';') {
-            let round = round_str.try_into()?;
+            let round = round_str
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
             rounds.push(round);
+            offset += round_str.len() + 1;
         }
         Ok(Game { id, rounds })
     }
@@ -159,8 +196,14 @@ impl FromStr for Input {
 
     fn from_str(text: &str) -> Result<Input, Self::Err> {
         let mut games: Vec<Game> = Vec::with_capacity(100);
-        for line in text.as_bytes().lines() {
-            games.push(line.try_into()?);
+        for (offset, line) in lines_with_offsets(text.as_bytes()) {
+            let game = line
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+            games.push(game);
+        }
+        if games.is_empty() {
+            return Err(ParseInputError::EmptyInput);
         }
         Ok(Self { games })
     }
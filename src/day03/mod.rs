@@ -1,54 +1,52 @@
-use bstr::ByteSlice;
+use thiserror::Error;
+
+use crate::aoclib::{grid_lines, CommonParseError};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 03");
-
-    println!("++Example");
-    let example = parse_input(EXAMPLE);
-    println!("|+-Part 1: {} (expected 4361)", part_1(&example));
-    println!("|'-Part 2: {} (expected 467835)", part_2(&example));
-
-    println!("++Input");
-    let input = parse_input(INPUT);
-    println!("|+-Part 1: {} (expected 532428)", part_1(&input));
-    println!("|'-Part 2: {} (expected 84051670)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input<'a>() -> Input<'a> {
-    parse_input(INPUT)
+    parse_input(INPUT).expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 3;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input<'a>;
+    type Transformed<'a> = Input<'a>;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        parse_input(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
 pub fn part_1(input: &Input) -> usize {
     let mut sum: usize = 0;
-    let empty_line = &[b'.'; 140][..input.lines[0].len()];
-    let height = input.lines.len();
-    let first = [[&empty_line, input.lines[0], input.lines[1]]].into_iter();
-    let middle = input.lines.array_windows().into_iter();
-    let last = [[
-        input.lines[height - 2],
-        input.lines[height - 1],
-        &empty_line,
-    ]]
-    .into_iter();
-    for [above, current, below] in []
-        .into_iter()
-        .chain(first)
-        .chain(middle.copied())
-        .chain(last)
-    {
+    for [above, current, below] in input.rows() {
         let mut value = 0;
         let mut len = 0;
         for (c, ch) in current.iter().chain(b".".iter()).copied().enumerate() {
@@ -88,22 +86,7 @@ fn is_symbol(ch: u8) -> bool {
 #[must_use]
 pub fn part_2(input: &Input) -> usize {
     let mut sum: usize = 0;
-    let empty_line = &[b'.'; 140][..input.lines[0].len()];
-    let height = input.lines.len();
-    let first = [[&empty_line, input.lines[0], input.lines[1]]].into_iter();
-    let middle = input.lines.array_windows().into_iter();
-    let last = [[
-        input.lines[height - 2],
-        input.lines[height - 1],
-        &empty_line,
-    ]]
-    .into_iter();
-    for [above, current, below] in []
-        .into_iter()
-        .chain(first)
-        .chain(middle.copied())
-        .chain(last)
-    {
+    for [above, current, below] in input.rows() {
         'symbol: for (c, ch) in current.iter().copied().enumerate() {
             if ch != b'*' {
                 continue;
@@ -156,10 +139,49 @@ pub fn part_2(input: &Input) -> usize {
 #[derive(Debug, Clone, Default)]
 pub struct Input<'a> {
     lines: Vec<&'a [u8]>,
+    /// Line of only `.`, as wide as the schematic
+    empty_line: Vec<u8>,
 }
 
-fn parse_input(text: &str) -> Input<'_> {
-    let mut lines = Vec::with_capacity(140);
-    lines.extend(text.as_bytes().lines());
-    Input { lines }
+impl Input<'_> {
+    /// Each line, with the lines above and below it. The schematic is padded with empty lines.
+    fn rows(&self) -> impl Iterator<Item = [&[u8]; 3]> {
+        let line = |r: Option<usize>| r.and_then(|r| self.lines.get(r).copied());
+        let empty = self.empty_line.as_slice();
+        (0..self.lines.len()).map(move |r| {
+            [
+                line(r.checked_sub(1)).unwrap_or(empty),
+                self.lines[r],
+                line(Some(r + 1)).unwrap_or(empty),
+            ]
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ParseInputError {
+    #[error("Unexpected character: '{0}'")]
+    InvalidChar(char),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
+}
+
+fn parse_input(text: &str) -> Result<Input<'_>, ParseInputError> {
+    let grid = grid_lines(text.as_bytes())?;
+    let width = grid[0].1.len();
+    let mut lines = Vec::with_capacity(grid.len());
+    for (offset, line) in grid {
+        if let Some(col) = line
+            .iter()
+            .position(|&ch| !ch.is_ascii_graphic() || ch.is_ascii_alphabetic())
+        {
+            let err = ParseInputError::InvalidChar(line[col] as char);
+            return Err(CommonParseError::invalid_cell(err, offset + col));
+        }
+        lines.push(line);
+    }
+    Ok(Input {
+        lines,
+        empty_line: vec![b'.'; width],
+    })
 }
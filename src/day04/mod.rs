@@ -1,36 +1,49 @@
 use bstr::ByteSlice;
 use thiserror::Error;
 
-use crate::aoclib::{parse_int, ParseIntError2};
+use crate::aoclib::{
+    fields_with_offsets, lines_with_offsets, parse_int, CommonParseError, ParseIntError2,
+};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 04");
-
-    println!("++Example");
-    let example = parse_input(EXAMPLE).expect("Parse input");
-    println!("|+-Part 1: {} (expected 13)", part_1(&example));
-    println!("|'-Part 2: {} (expected 30)", part_2(&example));
-
-    println!("++Input");
-    let input = parse_input(INPUT).expect("Parse input");
-    println!("|+-Part 1: {} (expected 23235)", part_1(&input));
-    println!("|'-Part 2: {} (expected 5920640)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Vec<Card> {
     parse_input(INPUT).expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 4;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Vec<Card>;
+    type Transformed<'a> = Vec<Card>;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        parse_input(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -44,11 +57,12 @@ pub fn part_1(input: &[Card]) -> usize {
 
 #[must_use]
 pub fn part_2(input: &[Card]) -> usize {
-    let counts = &mut [1; 256][..input.len()];
+    let counts = &mut vec![1; input.len()];
     let mut sum = 0;
     for (i, c) in input.iter().enumerate() {
         let c_count = counts[i];
-        for cnt in &mut counts[i + 1..=i + c.matches()] {
+        let end = (i + c.matches()).min(input.len() - 1);
+        for cnt in &mut counts[i + 1..=end] {
             *cnt += c_count;
         }
         sum += c_count;
@@ -77,82 +91,67 @@ impl Card {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
-    #[error("'Game ' prefix missing'")]
-    MissingFirstPrefix,
-    #[error("Missing ':' separator on first line")]
-    MissingFirstColon,
-    #[error("Missing '|' separator on first line")]
-    MissingFirstSeparator,
-    #[error("'Game ' prefix missing'")]
+    #[error("Input is empty")]
+    EmptyInput,
+    #[error("Expected line to start with 'Card '")]
     MissingPrefix,
     #[error("Missing ':' separator")]
     MissingColon,
     #[error("Missing '|' separator")]
     MissingSeparator,
-    #[error(transparent)]
+    #[error("Number too large: {0}, expected less than 128")]
+    NumberTooLarge(u8),
+    #[error("{0}")]
     ParseIntError(#[from] ParseIntError2),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 fn parse_input(text: &str) -> Result<Vec<Card>, ParseInputError> {
     let mut res: Vec<Card> = Vec::with_capacity(256);
-    let mut lines = text.as_bytes().lines();
-    if let Some(line) = lines.next() {
-        #[cfg(debug_assertions)]
-        if &line[..5] != b"Card " {
-            return Err(ParseInputError::MissingFirstPrefix);
-        }
-        let start_winning = if let Some(colon) = line[5..].find_byte(b':') {
-            colon + 7
-        } else {
-            return Err(ParseInputError::MissingFirstColon);
-        };
-        let end_winning = if let Some(bar) = line[start_winning..].find_byte(b'|') {
-            bar + start_winning - 1
-        } else {
-            return Err(ParseInputError::MissingFirstSeparator);
-        };
-        let start_have = end_winning + 3;
-        let end_have = line.len();
-        let winning = (start_winning..end_winning)
-            .step_by(3)
-            .map(|i| parse_int::<u8>(&line[i..i + 2]))
-            .try_fold(0_u128, |s, n| {
-                Result::<_, ParseInputError>::Ok(s | (1_u128 << n?))
-            })?;
-        let have = (start_have..end_have)
-            .step_by(3)
-            .map(|i| parse_int::<u8>(&line[i..i + 2]))
-            .try_fold(0_u128, |s, n| {
-                Result::<_, ParseInputError>::Ok(s | (1_u128 << n?))
-            })?;
-        res.push(Card { winning, have });
-        for line in lines {
-            #[cfg(debug_assertions)]
-            if &line[..5] != b"Card " {
-                return Err(ParseInputError::MissingPrefix);
-            }
-            #[cfg(debug_assertions)]
-            if &line[start_winning - 2..start_winning] != b": " {
-                return Err(ParseInputError::MissingColon);
-            }
-            #[cfg(debug_assertions)]
-            if &line[start_have - 3..start_have] != b" | " {
-                return Err(ParseInputError::MissingSeparator);
-            }
-            let winning = (start_winning..end_winning)
-                .step_by(3)
-                .map(|i| parse_int::<u8>(&line[i..i + 2]))
-                .try_fold(0_u128, |s, n| {
-                    Result::<_, ParseInputError>::Ok(s | (1_u128 << n?))
-                })?;
-            let have = (start_have..end_have)
-                .step_by(3)
-                .map(|i| parse_int::<u8>(&line[i..i + 2]))
-                .try_fold(0_u128, |s, n| {
-                    Result::<_, ParseInputError>::Ok(s | (1_u128 << n?))
-                })?;
-            res.push(Card { winning, have });
-        }
+    for (offset, line) in lines_with_offsets(text.as_bytes()) {
+        let card = parse_card(line).map_err(|err| CommonParseError::invalid_part(err, offset))?;
+        res.push(card);
+    }
+    if res.is_empty() {
+        return Err(ParseInputError::EmptyInput);
     }
     Ok(res)
 }
+
+fn parse_card(line: &[u8]) -> Result<Card, ParseInputError> {
+    const PREFIX: &[u8] = b"Card ";
+    let line = line
+        .strip_prefix(PREFIX)
+        .ok_or(ParseInputError::MissingPrefix)?;
+    let (id, numbers) = line
+        .split_once_str(b":")
+        .ok_or(ParseInputError::MissingColon)?;
+    let (winning, have) = numbers
+        .split_once_str(b"|")
+        .ok_or(ParseInputError::MissingSeparator)?;
+    let winning_offset = PREFIX.len() + id.len() + 1;
+    let have_offset = winning_offset + winning.len() + 1;
+    Ok(Card {
+        winning: parse_numbers(winning)
+            .map_err(|err| CommonParseError::invalid_part(err, winning_offset))?,
+        have: parse_numbers(have)
+            .map_err(|err| CommonParseError::invalid_part(err, have_offset))?,
+    })
+}
+
+/// Set of the numbers in a whitespace separated list, as a bit mask
+fn parse_numbers(text: &[u8]) -> Result<u128, ParseInputError> {
+    let mut set = 0;
+    for (start, number) in fields_with_offsets(text) {
+        let value: u8 = parse_int(number).map_err(|err| err.offset_by(start))?;
+        if value >= 128 {
+            return Err(CommonParseError::invalid_part(
+                ParseInputError::NumberTooLarge(value),
+                start,
+            ));
+        }
+        set |= 1 << value;
+    }
+    Ok(set)
+}
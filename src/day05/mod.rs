@@ -1,39 +1,48 @@
-use bstr::ByteSlice;
 use std::cmp::Ordering;
 use std::fmt::Debug;
 use thiserror::Error;
 
-use crate::aoclib::{parse_int, ParseIntError2};
+use crate::aoclib::{lines_with_offsets, parse_int, CommonParseError, ParseIntError2};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 05");
-
-    println!("++Example");
-    let example = parse_input(EXAMPLE).expect("Example input parsed");
-    println!("|+-Part 1: {} (expected 35)", part_1(&example));
-    println!("|'-Part 2: {} (expected 46)", part_2(&example));
-
-    println!("++Input");
-    let input = parse_input(INPUT).expect("Real input parsed");
-    println!("|+-Part 1: {} (expected 174137457)", part_1(&input));
-    println!("|'-Part 2: {} (expected 1493866)", part_2(&input));
-
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     parse_input(INPUT).expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 5;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseError;
+    type Answer1 = isize;
+    type Answer2 = isize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        parse_input(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<isize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<isize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -202,10 +211,9 @@ fn numfmt(x: isize) -> String {
 }
 
 #[derive(Debug, Error)]
-enum ParseError {
+pub enum ParseError {
     #[error("Input is empty")]
     EmptyInput,
-    #[cfg(debug_assertions)]
     #[error("First line should start with 'Seeds:'")]
     SeedSuffix,
     #[error("The first number, source_start, is missing.")]
@@ -216,6 +224,8 @@ enum ParseError {
     MissingLen,
     #[error("One of the numbers could not be parsed as an integer: {0}")]
     NotInteger(#[from] ParseIntError2),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
     #[error("The line contains more values than expected")]
     #[cfg(debug_assertions)]
     ExtraneousValues,
@@ -226,9 +236,16 @@ impl<'a> TryFrom<&'a [u8]> for Mapping {
 
     fn try_from(s: &'a [u8]) -> Result<Self, Self::Error> {
         let mut it = s.split(|&ch| ch == b' ');
-        let destination_start: isize = parse_int(it.next().ok_or(ParseError::MissingDestination)?)?;
-        let source_start: isize = parse_int(it.next().ok_or(ParseError::MissingSource)?)?;
-        let len: isize = parse_int(it.next().ok_or(ParseError::MissingLen)?)?;
+        let mut offset = 0;
+        let mut next = |missing: ParseError| -> Result<isize, ParseError> {
+            let num_str = it.next().ok_or(missing)?;
+            let value = parse_int(num_str).map_err(|err| err.offset_by(offset))?;
+            offset += num_str.len() + 1;
+            Ok(value)
+        };
+        let destination_start = next(ParseError::MissingDestination)?;
+        let source_start = next(ParseError::MissingSource)?;
+        let len = next(ParseError::MissingLen)?;
         #[cfg(debug_assertions)]
         if it.next().is_some() {
             return Err(ParseError::ExtraneousValues);
@@ -250,15 +267,17 @@ pub struct Input {
 }
 
 fn parse_input(text: &str) -> Result<Input, ParseError> {
-    let mut lines = text.as_bytes().lines();
-    let first_line = lines.next().ok_or(ParseError::EmptyInput)?;
-    #[cfg(debug_assertions)]
-    if &first_line[..7] != b"seeds: " {
-        return Err(ParseError::SeedSuffix);
-    }
+    const PREFIX: &[u8] = b"seeds: ";
+    let mut lines = lines_with_offsets(text.as_bytes());
+    let (_, first_line) = lines.next().ok_or(ParseError::EmptyInput)?;
+    let seeds_str = first_line
+        .strip_prefix(PREFIX)
+        .ok_or(ParseError::SeedSuffix)?;
     let mut seeds = Vec::with_capacity(20);
-    for num_str in first_line[7..].split(|&ch| ch == b' ') {
-        seeds.push(parse_int(num_str)?);
+    let mut offset = PREFIX.len();
+    for num_str in seeds_str.split(|&ch| ch == b' ') {
+        seeds.push(parse_int(num_str).map_err(|err| err.offset_by(offset))?);
+        offset += num_str.len() + 1;
     }
     let mut seed_ranges = Vec::with_capacity(seeds.len() / 2);
     for &[a, b] in seeds.array_chunks() {
@@ -270,7 +289,7 @@ fn parse_input(text: &str) -> Result<Input, ParseError> {
     let mut current: Vec<Mapping> = Vec::with_capacity(50);
     let mut is_header = true;
 
-    for line in lines {
+    for (offset, line) in lines {
         if line.is_empty() {
             is_header = true;
             continue;
@@ -285,7 +304,10 @@ fn parse_input(text: &str) -> Result<Input, ParseError> {
                 mappings.push(tmp);
             }
         } else {
-            current.push(line.try_into()?);
+            let mapping = line
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+            current.push(mapping);
         }
     }
     current.sort_unstable();
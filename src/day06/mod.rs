@@ -1,55 +1,77 @@
-use std::num::ParseIntError;
+use thiserror::Error;
+
+use crate::aoclib::{
+    fields_with_offsets, lines_with_offsets, parse_int, CommonParseError, ParseIntError2,
+};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 06");
-
-    println!("++Example");
-    let example = parse_input(EXAMPLE);
-    println!("|+-Part 1: {} (expected 288)", part_1(&example));
-    println!("|'-Part 2: {} (expected 71503)", part_2(&example));
-
-    println!("++Input");
-    let input = parse_input(INPUT);
-    println!("|+-Part 1: {} (expected 4403592)", part_1(&input));
-    println!("|'-Part 2: {} (expected 38017587)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
-    parse_input(INPUT)
+    parse_input(INPUT).expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 6;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = u64;
+    type Answer2 = u64;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        parse_input(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<u64> {
+        part_1(input)
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<u64> {
+        part_2(input)
+    }
 }
 
+/// Returns `None` if the product overflows.
 #[must_use]
-pub fn part_1(input: &Input) -> i64 {
-    let mut product = 1;
+pub fn part_1(input: &Input) -> Option<u64> {
+    let mut product: u64 = 1;
     for race in &input.races {
-        product *= race.score();
+        product = product.checked_mul(race.score()?)?;
     }
-    product
+    Some(product)
 }
 
+/// Returns `None` if the combined time or distance overflows.
 #[must_use]
-pub fn part_2(input: &Input) -> i64 {
+pub fn part_2(input: &Input) -> Option<u64> {
     let mut combined = Race::default();
-    let scales = [1, 10, 100, 1_000, 10_000];
     for race in &input.races {
-        combined.time = combined.time * scales[race.time_len] + race.time;
-        combined.distance = combined.distance * scales[race.distance_len] + race.distance;
+        combined.time = concat(combined.time, race.time, race.time_len)?;
+        combined.distance = concat(combined.distance, race.distance, race.distance_len)?;
     }
     combined.score()
 }
 
+/// The digits of `low`, which has `low_len` of them, appended to those of `high`
+fn concat(high: u64, low: u64, low_len: usize) -> Option<u64> {
+    let scale = 10_u64.checked_pow(u32::try_from(low_len).ok()?)?;
+    high.checked_mul(scale)?.checked_add(low)
+}
+
 #[derive(Debug, Clone)]
 pub struct Input {
     races: Vec<Race>,
@@ -57,52 +79,101 @@ pub struct Input {
 
 #[derive(Debug, Clone, Default)]
 struct Race {
-    time: i64,
+    time: u64,
     time_len: usize,
-    distance: i64,
+    distance: u64,
     distance_len: usize,
 }
 
 impl Race {
-    pub fn from_str(time_str: &str, distance_str: &str) -> Result<Race, ParseIntError> {
-        Ok(Self {
-            time: time_str.parse()?,
-            time_len: time_str.len(),
-            distance: distance_str.parse()?,
-            distance_len: distance_str.len(),
-        })
+    pub fn new(time: u64, time_len: usize, distance: u64, distance_len: usize) -> Self {
+        Self {
+            time,
+            time_len,
+            distance,
+            distance_len,
+        }
     }
 
-    pub fn score(&self) -> i64 {
-        #[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
-        let s = ((self.time * self.time - 4 * self.distance - 4) as f64).sqrt() as i64;
-        let low = (self.time - s + 1) / 2;
-        let high = (self.time + s) / 2;
-        high - low + 1
+    /// Number of ways to beat the record. Holding the button for `t` wins if
+    /// `t * (time - t) > distance`, that is if `(2t - time)^2 <= time^2 - 4 * distance - 4`.
+    /// Returns `None` if the number does not fit.
+    pub fn score(&self) -> Option<u64> {
+        let time = u128::from(self.time);
+        let Some(disc) = (time * time).checked_sub(4 * u128::from(self.distance) + 4) else {
+            return Some(0);
+        };
+        let s = disc.isqrt();
+        let low = (time - s).div_ceil(2);
+        let high = time.midpoint(s);
+        u64::try_from(high - low + 1).ok()
     }
 }
 
-fn parse_input(text: &str) -> Input {
-    let mut lines = text.lines();
-
-    let time_it = lines
-        .next()
-        .expect("two lines")
-        .strip_prefix("Time:")
-        .expect("time prefix")
-        .split_ascii_whitespace();
-
-    let distance_it = lines
-        .next()
-        .expect("two lines")
-        .strip_prefix("Distance:")
-        .expect("distance prefix")
-        .split_ascii_whitespace();
-
-    let races = time_it
-        .zip(distance_it)
-        .map(|(t, d)| Race::from_str(t, d).expect("integers"))
-        .collect::<Vec<_>>();
-
-    Input { races }
+#[derive(Debug, Error)]
+pub enum ParseInputError {
+    #[error("Missing line: '{0}'")]
+    MissingLine(&'static str),
+    #[error("Expected line to start with '{0}'")]
+    MissingPrefix(&'static str),
+    #[error("Not an integer: {0}")]
+    InvalidInteger(#[from] ParseIntError2),
+    #[error("No races")]
+    NoRaces,
+    #[error("{times} times but {distances} distances")]
+    CountMismatch { times: usize, distances: usize },
+    #[error("Unexpected line after the distances")]
+    ExtraLine,
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
+}
+
+fn parse_input(text: &str) -> Result<Input, ParseInputError> {
+    let mut lines = lines_with_offsets(text.as_bytes());
+    let times = parse_line(lines.next(), "Time:")?;
+    let distance_line = lines.next();
+    let distances = parse_line(distance_line, "Distance:")?;
+    let offset = distance_line.map_or(0, |(offset, _)| offset);
+    if times.is_empty() {
+        return Err(ParseInputError::NoRaces);
+    }
+    if times.len() != distances.len() {
+        let err = ParseInputError::CountMismatch {
+            times: times.len(),
+            distances: distances.len(),
+        };
+        return Err(CommonParseError::invalid_part(err, offset));
+    }
+    if let Some((offset, _)) = lines.find(|(_, line)| !line.trim_ascii().is_empty()) {
+        return Err(CommonParseError::invalid_part(
+            ParseInputError::ExtraLine,
+            offset,
+        ));
+    }
+    let races = times
+        .into_iter()
+        .zip(distances)
+        .map(|((time, time_len), (distance, distance_len))| {
+            Race::new(time, time_len, distance, distance_len)
+        })
+        .collect();
+    Ok(Input { races })
+}
+
+/// The numbers after `prefix` in `line`, with the number of digits in each
+fn parse_line(
+    line: Option<(usize, &[u8])>,
+    prefix: &'static str,
+) -> Result<Vec<(u64, usize)>, ParseInputError> {
+    let (offset, line) = line.ok_or(ParseInputError::MissingLine(prefix))?;
+    let numbers = line.strip_prefix(prefix.as_bytes()).ok_or_else(|| {
+        CommonParseError::invalid_part(ParseInputError::MissingPrefix(prefix), offset)
+    })?;
+    fields_with_offsets(numbers)
+        .map(|(start, number)| {
+            let value =
+                parse_int(number).map_err(|err| err.offset_by(offset + prefix.len() + start))?;
+            Ok((value, number.len()))
+        })
+        .collect()
 }
@@ -1,36 +1,46 @@
-use std::num::ParseIntError;
-use std::str::FromStr;
-
 use thiserror::Error;
 
+use crate::aoclib::{lines_with_offsets, parse_int, CommonParseError, ParseIntError2};
+use crate::solution::Solution;
+
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 07");
-
-    println!("++Example");
-    let example = parse_input(EXAMPLE);
-    println!("|+-Part 1: {} (expected 6440)", part_1(&example));
-    println!("|'-Part 2: {} (expected 5905)", part_2(&example));
-
-    println!("++Input");
-    let input = parse_input(INPUT);
-    println!("|+-Part 1: {} (expected 253313241)", part_1(&input));
-    println!("|'-Part 2: {} (expected 253362743)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Vec<Input> {
-    parse_input(INPUT)
+    parse_input(INPUT).expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 7;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Vec<Input>;
+    type Transformed<'a> = Vec<Input>;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = u64;
+    type Answer2 = u64;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        parse_input(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<u64> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<u64> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -57,6 +67,8 @@ pub fn part_2(input: &[Input]) -> u64 {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
     #[error("Invalid card: {0}")]
     InvalidCard(char),
     #[error("Input line too short")]
@@ -64,7 +76,9 @@ pub enum ParseInputError {
     #[error("No separator (' ')")]
     MissingSeparator,
     #[error("Invalid bid integer: {0}")]
-    InvalidBid(#[from] ParseIntError),
+    InvalidBid(#[from] ParseIntError2),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
@@ -189,30 +203,38 @@ impl Input {
     }
 }
 
-impl FromStr for Input {
-    type Err = ParseInputError;
+impl TryFrom<&[u8]> for Input {
+    type Error = ParseInputError;
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let bytes = s.as_bytes();
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
         if bytes.len() < 7 {
             return Err(ParseInputError::TooShort);
         }
         let mut res = Self::default();
         for (i, &b) in bytes[0..5].iter().enumerate() {
-            res.cards[i] = b.try_into()?;
+            res.cards[i] = b
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_cell(err, i))?;
         }
         if bytes[5] != b' ' {
-            return Err(ParseInputError::MissingSeparator);
+            let err = ParseInputError::MissingSeparator;
+            return Err(CommonParseError::invalid_cell(err, 5));
         }
-        res.bet = s[6..].parse()?;
+        res.bet = parse_int(&bytes[6..]).map_err(|err| err.offset_by(6))?;
         Ok(res)
     }
 }
 
-fn parse_input(text: &str) -> Vec<Input> {
+fn parse_input(text: &str) -> Result<Vec<Input>, ParseInputError> {
     let mut res: Vec<Input> = Vec::with_capacity(1000);
-    for line in text.lines() {
-        res.push(line.parse().expect("Valid input"));
+    for (offset, line) in lines_with_offsets(text.as_bytes()) {
+        let hand = line
+            .try_into()
+            .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+        res.push(hand);
+    }
+    if res.is_empty() {
+        return Err(ParseInputError::EmptyInput);
     }
-    res
+    Ok(res)
 }
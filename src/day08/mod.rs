@@ -1,87 +1,132 @@
 use std::str::FromStr;
 
-use bstr::ByteSlice;
 use smallvec::SmallVec;
 use thiserror::Error;
 
-use crate::aoclib::lcm;
+use crate::aoclib::{crt_from, lines_with_offsets, scan, CommonParseError, Cycle, Turn};
+use crate::solution::Solution;
 
 const EXAMPLE1: &str = include_str!("example1.txt");
 const EXAMPLE2: &str = include_str!("example2.txt");
 const EXAMPLE3: &str = include_str!("example3.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 07");
+#[must_use]
+pub fn parse_test_input() -> Input {
+    INPUT.parse().expect("Real input")
+}
 
-    println!("++Example1");
-    let example1 = EXAMPLE1.parse().expect("Parse example 1");
-    println!("|+-Part 1: {} (expected 2)", part_1(&example1));
+pub struct Puzzle;
 
-    println!("++Example2");
-    let example2 = EXAMPLE2.parse().expect("Parse example 1");
-    println!("|+-Part 1: {} (expected 6)", part_1(&example2));
+impl Solution for Puzzle {
+    const DAY: u8 = 8;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] = &[
+        ("example1", EXAMPLE1, ()),
+        ("example2", EXAMPLE2, ()),
+        ("example3", EXAMPLE3, ()),
+        ("input", INPUT, ()),
+    ];
+    const ANSWERS: &'static str = include_str!("answers.toml");
 
-    println!("++Example3");
-    let example3 = EXAMPLE3.parse().expect("Parse example 3");
-    println!("|+-Part 2: {} (expected 6)", part_2(&example3));
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = u64;
+    type Params = ();
 
-    println!("++Input");
-    let input = INPUT.parse().expect("Real input");
-    println!("|+-Part 1: {} (expected 15517)", part_1(&input));
-    println!("|'-Part 2: {} (expected 14935034899483)", part_2(&input));
-    println!("')");
-}
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
 
-#[must_use]
-pub fn parse_test_input() -> Input {
-    INPUT.parse().expect("Real input")
-}
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        part_1(input)
+    }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<u64> {
+        part_2(input)
+    }
 }
 
+/// Returns `None` if there is no `AAA` or `ZZZ` node, or `ZZZ` can not be reached. After one step
+/// per (node, instruction) state the walk has started repeating.
 #[must_use]
-pub fn part_1(input: &Input) -> usize {
-    let mut node = input.start_ix;
+pub fn part_1(input: &Input) -> Option<usize> {
+    let (mut node, end_ix) = (input.start_ix?, input.end_ix?);
+    let states = input.node_count * input.instructions.len();
     for (i, mov) in input.instructions.iter().copied().cycle().enumerate() {
-        if node == input.end_ix {
-            return i;
+        if node == end_ix {
+            return Some(i);
         }
-        node = match mov {
-            Dir::Left => input.nodes[node].left_ix,
-            Dir::Right => input.nodes[node].right_ix,
-        };
+        if i == states {
+            return None;
+        }
+        node = input.next(node, mov);
     }
     unreachable!()
 }
 
+/// Each ghost ends up looping through the same (node, instruction) states. Before all of them
+/// loop the steps are checked one by one, after that each ghost is at an end node at the steps
+/// congruent to one of the end nodes in its loop, which [`crt_from`] combines.
+///
+/// Returns `None` if there are no ghosts or they are never all at an end node at the same time.
 #[must_use]
-pub fn part_2(input: &Input) -> u64 {
-    let mut res = 1;
-    for &start_ix in &input.start_ixs {
-        let dist = distance_to_end(input, start_ix);
-        res = lcm(res, dist as u64);
+pub fn part_2(input: &Input) -> Option<u64> {
+    let len = input.instructions.len();
+    let cycles: SmallVec<[_; 6]> = input
+        .start_ixs
+        .iter()
+        .map(|&start_ix| {
+            Cycle::find((start_ix, 0), |(ix, step)| {
+                *ix = input.next(*ix, input.instructions[*step]);
+                *step = (*step + 1) % len;
+            })
+        })
+        .collect();
+    let is_end = |cycle: &Cycle<(usize, usize)>, n| input.nodes[cycle.nth(n).0].is_end;
+
+    let prefix_len = cycles.iter().map(Cycle::prefix_len).max()?;
+    if let Some(steps) = (0..prefix_len).find(|&n| cycles.iter().all(|cycle| is_end(cycle, n))) {
+        return u64::try_from(steps).ok();
     }
-    res
-}
 
-fn distance_to_end(input: &Input, start_ix: usize) -> usize {
-    let mut ix = start_ix;
-    for (i, mov) in input.instructions.iter().copied().cycle().enumerate() {
-        if input.nodes[ix].is_end {
-            return i;
+    let ends: SmallVec<[Vec<(i128, i128)>; 6]> = cycles
+        .iter()
+        .map(|cycle| {
+            let period = cycle.period();
+            (prefix_len..prefix_len + period)
+                .filter(|&n| is_end(cycle, n))
+                .map(|n| (n as i128, period as i128))
+                .collect()
+        })
+        .collect();
+    let mut choice = vec![0; ends.len()];
+    let mut best = None;
+    loop {
+        let congruences = ends
+            .iter()
+            .zip(&choice)
+            .map(|(ends, &ix)| ends.get(ix).copied());
+        // The first solution where all ghosts are in their loops
+        let solution = congruences
+            .collect::<Option<Vec<_>>>()
+            .and_then(|congruences| crt_from(congruences, prefix_len as i128));
+        if let Some((steps, _)) = solution {
+            best = Some(best.map_or(steps, |best: i128| best.min(steps)));
         }
-        ix = match mov {
-            Dir::Left => input.nodes[ix].left_ix,
-            Dir::Right => input.nodes[ix].right_ix,
+        // Next combination of ends
+        let Some(ix) = (0..ends.len()).find(|&ix| choice[ix] + 1 < ends[ix].len()) else {
+            break;
         };
+        choice[ix] += 1;
+        choice[..ix].fill(0);
     }
-    unreachable!()
+    u64::try_from(best?).ok()
 }
 
 #[derive(Debug, Error, Clone)]
@@ -90,29 +135,18 @@ pub enum ParseInputError {
     EmptyInput,
     #[error("Invalid instruction: '{0}'")]
     InvalidInstruction(char),
+    #[error("No instructions on the first line")]
+    NoInstructions,
     #[error("Missing blank separator line")]
     MissingSeparatorLine,
     #[error("Node line does not match 'NAME = (NAME, NAME)'")]
     NodeSyntaxError,
-}
-
-#[derive(Debug, Clone, Copy)]
-#[repr(u8)]
-#[allow(dead_code)]
-enum Dir {
-    Left = b'L' & 2,
-    Right = b'R' & 2,
-}
-
-impl TryFrom<u8> for Dir {
-    type Error = ParseInputError;
-
-    fn try_from(ch: u8) -> Result<Self, Self::Error> {
-        Ok(match ch {
-            b'L' | b'R' => unsafe { std::mem::transmute(ch & 2) },
-            _ => return Err(ParseInputError::InvalidInstruction(ch as char)),
-        })
-    }
+    #[error("Node '{0}' is defined twice")]
+    DuplicateNode(String),
+    #[error("Node '{0}' is not defined")]
+    UndefinedNode(String),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -134,13 +168,25 @@ impl Node {
 
 #[derive(Debug, Clone)]
 pub struct Input {
-    instructions: SmallVec<[Dir; 263]>,
+    instructions: SmallVec<[Turn; 263]>,
     nodes: Vec<Node>,
-    start_ix: usize,
-    end_ix: usize,
+    /// Number of defined nodes
+    node_count: usize,
+    start_ix: Option<usize>,
+    end_ix: Option<usize>,
     start_ixs: SmallVec<[usize; 6]>,
 }
 
+impl Input {
+    /// The node reached from node `ix` by `mov`, which is parsed as left or right
+    fn next(&self, ix: usize, mov: Turn) -> usize {
+        match mov {
+            Turn::Left => self.nodes[ix].left_ix,
+            _ => self.nodes[ix].right_ix,
+        }
+    }
+}
+
 impl FromStr for Input {
     type Err = ParseInputError;
 
@@ -154,61 +200,78 @@ impl FromStr for Input {
             }
         }
 
-        let mut lines = s.as_bytes().lines();
+        let mut lines = lines_with_offsets(s.as_bytes());
 
         let mut instructions = SmallVec::new();
-        for &ch in lines.next().ok_or(ParseInputError::EmptyInput)? {
-            instructions.push(Dir::try_from(ch)?);
+        let (_, first_line) = lines.next().ok_or(ParseInputError::EmptyInput)?;
+        for (col, &ch) in first_line.iter().enumerate() {
+            let turn = Turn::from_lr(ch).ok_or_else(|| {
+                let err = ParseInputError::InvalidInstruction(ch as char);
+                CommonParseError::invalid_cell(err, col)
+            })?;
+            instructions.push(turn);
+        }
+        if instructions.is_empty() {
+            return Err(ParseInputError::NoInstructions);
         }
 
         match lines.next() {
-            Some([]) => (),
+            Some((_, [])) => (),
             _ => return Err(ParseInputError::MissingSeparatorLine),
         };
 
         let mut nodes = vec![Node::default(); 1 << 15];
-        let mut start_ix = usize::MAX;
-        let mut end_ix = usize::MAX;
+        let mut defined = vec![false; 1 << 15];
+        let mut edges = Vec::new();
+        let mut start_ix = None;
+        let mut end_ix = None;
         let mut start_ixs = SmallVec::new();
-        for line in lines {
-            // AAA = (BBB, CCC)
-            if line.len() != 16 {
-                return Err(ParseInputError::NodeSyntaxError);
-            }
-            let name = &line[0..3];
-            #[cfg(debug_assertions)]
-            if &line[3..7] != b" = (" {
-                return Err(ParseInputError::NodeSyntaxError);
-            }
-            let left = &line[7..10];
-            #[cfg(debug_assertions)]
-            if &line[10..12] != b", " {
-                return Err(ParseInputError::NodeSyntaxError);
-            }
-            let right = &line[12..15];
-            #[cfg(debug_assertions)]
-            if &line[15..] != b")" {
-                return Err(ParseInputError::NodeSyntaxError);
+        for (offset, line) in lines {
+            let (name, left, right) = scan!(line => ident " = (" ident ", " ident ")")
+                .map_err(|err| err.offset_by(offset))?;
+            if [name, left, right].iter().any(|name| name.len() != 3) {
+                return Err(CommonParseError::invalid_part(
+                    ParseInputError::NodeSyntaxError,
+                    offset,
+                ));
             }
 
             let mut node = Node::new(hash(left), hash(right));
             let ix = hash(name);
+            if std::mem::replace(&mut defined[ix], true) {
+                let name = String::from_utf8_lossy(name).into_owned();
+                return Err(CommonParseError::invalid_part(
+                    ParseInputError::DuplicateNode(name),
+                    offset,
+                ));
+            }
             if name[2] == b'A' {
                 if name == b"AAA" {
-                    start_ix = ix;
+                    start_ix = Some(ix);
                 }
                 start_ixs.push(ix);
             } else if name[2] == b'Z' {
                 if name == b"ZZZ" {
-                    end_ix = ix;
+                    end_ix = Some(ix);
                 }
                 node.is_end = true;
             }
             nodes[ix] = node;
+            edges.push((offset, left, right));
+        }
+        for &(offset, left, right) in &edges {
+            if let Some(&name) = [left, right].iter().find(|&&name| !defined[hash(name)]) {
+                let name = String::from_utf8_lossy(name).into_owned();
+                return Err(CommonParseError::invalid_part(
+                    ParseInputError::UndefinedNode(name),
+                    offset,
+                ));
+            }
         }
         Ok(Self {
             instructions,
             nodes,
+            node_count: edges.len(),
             start_ix,
             end_ix,
             start_ixs,
@@ -3,36 +3,47 @@ use num_traits::Num;
 use smallvec::SmallVec;
 use thiserror::Error;
 
-use crate::aoclib::{parse_int, ParseIntError2};
+use crate::aoclib::{lines_with_offsets, parse_int, CommonParseError, ParseIntError2};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 09");
-
-    println!("++Example");
-    let example = parse_input(EXAMPLE).expect("Parse example");
-    println!("|+-Part 1: {} (expected 114)", part_1(&example));
-    println!("|'-Part 2: {} (expected 2)", part_2(&example));
-
-    println!("++Input");
-    let input = parse_input(INPUT).expect("Parse real input");
-    println!("|+-Part 1: {} (expected 1939607039)", part_1(&input));
-    println!("|'-Part 2: {} (expected 1041)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Vec<Input> {
     parse_input(INPUT).expect("Parse real input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_input(INPUT).expect("Parse real input");
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 9;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Vec<Input>;
+    type Transformed<'a> = Vec<Input>;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = i64;
+    type Answer2 = i64;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        parse_input(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<i64> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<i64> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -79,8 +90,12 @@ pub struct Input {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
     #[error("Not an integer: {0}")]
     NotAnInteger(#[from] ParseIntError2),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 impl<'a> TryFrom<&'a [u8]> for Input {
@@ -96,10 +111,10 @@ impl<'a> TryFrom<&'a [u8]> for Input {
         start = 0;
         let mut values = Vec::with_capacity(count);
         while let Some(ix) = line[start..].find_byte(b' ') {
-            values.push(parse_int(&line[start..start + ix])?);
+            values.push(parse_int(&line[start..start + ix]).map_err(|err| err.offset_by(start))?);
             start += ix + 1;
         }
-        values.push(parse_int(&line[start..])?);
+        values.push(parse_int(&line[start..]).map_err(|err| err.offset_by(start))?);
         Ok(Self { values })
     }
 }
@@ -107,8 +122,14 @@ impl<'a> TryFrom<&'a [u8]> for Input {
 fn parse_input(text: &str) -> Result<Vec<Input>, ParseInputError> {
     let line_count = text.as_bytes().lines().count();
     let mut res: Vec<Input> = Vec::with_capacity(line_count);
-    for line in text.as_bytes().lines() {
-        res.push(line.try_into()?);
+    for (offset, line) in lines_with_offsets(text.as_bytes()) {
+        let input = line
+            .try_into()
+            .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+        res.push(input);
+    }
+    if res.is_empty() {
+        return Err(ParseInputError::EmptyInput);
     }
     Ok(res)
 }
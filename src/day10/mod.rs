@@ -4,7 +4,8 @@ use std::str::FromStr;
 
 use thiserror::Error;
 
-use crate::aoclib::{CommonParseError, Dir, Grid, Pos};
+use crate::aoclib::{CellByte, CommonParseError, Dir, Grid, Pos};
+use crate::solution::Solution;
 
 const EXAMPLE1: &str = include_str!("example1.txt");
 const EXAMPLE2: &str = include_str!("example2.txt");
@@ -12,94 +13,79 @@ const EXAMPLE3: &str = include_str!("example3.txt");
 const EXAMPLE4: &str = include_str!("example4.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 10");
-
-    println!("++Example1");
-    let example1 = EXAMPLE1.parse().expect("Parse example 1");
-    println!("|+-Part 1: {} (expected 4)", part_1(&example1));
-
-    println!("++Example2");
-    let example2 = EXAMPLE2.parse().expect("Parse example 2");
-    println!("|+-Part 1: {} (expected 8)", part_1(&example2));
-
-    println!("++Example3");
-    let example3 = EXAMPLE3.parse().expect("Parse example 3");
-    println!("|+-Part 2: {} (expected 4)", part_2(&example3));
-
-    println!("++Example4");
-    let example4 = EXAMPLE4.parse().expect("Parse example 4");
-    println!("|+-Part 2: {} (expected 10)", part_2(&example4));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 6717)", part_1(&input));
-    println!("|'-Part 2: {} (expected 381)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Real input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 10;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] = &[
+        ("example1", EXAMPLE1, ()),
+        ("example2", EXAMPLE2, ()),
+        ("example3", EXAMPLE3, ()),
+        ("example4", EXAMPLE4, ()),
+        ("input", INPUT, ()),
+    ];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = isize;
+    type Answer2 = isize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<isize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<isize> {
+        Some(part_2(input))
+    }
 }
 
+/// The farthest point of the loop is the one with the largest distance from the start
 #[must_use]
 pub fn part_1(input: &Input) -> isize {
-    walk_path(input).perimiter / 2
+    let distances = input.grid.bfs([input.start], |from, to| {
+        Dir::ALL
+            .into_iter()
+            .any(|dir| input.walk(from, dir) == Some(to))
+    });
+    let max_dist = distances
+        .into_distances()
+        .iter()
+        .filter_map(|(_, &dist)| dist)
+        .max();
+    isize::try_from(max_dist.unwrap_or(0)).unwrap()
 }
 
 #[must_use]
 pub fn part_2(input: &Input) -> isize {
-    walk_path(input).inner_area
+    inner_area(input)
 }
 
-fn walk_path(input: &Input) -> PathResult {
-    let enter = input
-        .neighbors(input.start)
-        .into_iter()
-        .flatten()
-        .next()
-        .unwrap()
-        .0
-        .reverse();
-
+/// Number of tiles enclosed by the loop, from its area by the shoelace formula and Pick's theorem
+fn inner_area(input: &Input) -> isize {
     let mut area = 0;
     let mut perimiter = 0;
-    let mut pos = input.start;
-    let mut dir = enter;
-    loop {
-        let (next_dir, next) = input
-            .neighbors(pos)
-            .into_iter()
-            .flatten()
-            .find(|(next_dir, _)| next_dir.reverse() != dir)
-            .unwrap();
+    let closed = input.walk_loop(|pos, next| {
         area += pos.col() * next.row() - next.col() * pos.row(); // Sholace formula
         perimiter += 1;
-        pos = next;
-        dir = next_dir;
-        if pos == input.start {
-            break;
-        }
-    }
-    let inner_area = (area.abs() - perimiter) / 2 + 1;
-    PathResult {
-        inner_area,
-        perimiter,
-    }
-}
-
-#[derive(Debug)]
-struct PathResult {
-    inner_area: isize,
-    perimiter: isize,
+    });
+    debug_assert!(closed, "The loop is checked when parsing");
+    (area.abs() - perimiter) / 2 + 1
 }
 
 /// Underectional pipes
@@ -155,6 +141,21 @@ impl TryFrom<u8> for Pipe {
     }
 }
 
+impl CellByte for Pipe {
+    fn to_byte(self) -> u8 {
+        match self {
+            Pipe::X => b'.',
+            Pipe::NS => b'|',
+            Pipe::EW => b'-',
+            Pipe::NE => b'L',
+            Pipe::NW => b'J',
+            Pipe::SW => b'7',
+            Pipe::SE => b'F',
+            Pipe::S => b'S',
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Input {
     grid: Grid<Pipe>,
@@ -181,6 +182,32 @@ impl Input {
             try { (Dir::W, self.walk(pos, Dir::W)?) },
         ]
     }
+
+    /// Follow the loop from the start, calling `step` with each pipe and the one after it.
+    /// Returns `false` if the loop is broken before it gets back to the start.
+    fn walk_loop(&self, mut step: impl FnMut(Pos, Pos)) -> bool {
+        let Some((enter, _)) = self.neighbors(self.start).into_iter().flatten().next() else {
+            return false;
+        };
+        let mut pos = self.start;
+        let mut dir = enter.reverse();
+        loop {
+            let Some((next_dir, next)) = self
+                .neighbors(pos)
+                .into_iter()
+                .flatten()
+                .find(|(next_dir, _)| next_dir.reverse() != dir)
+            else {
+                return false;
+            };
+            step(pos, next);
+            pos = next;
+            dir = next_dir;
+            if pos == self.start {
+                return true;
+            }
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -189,9 +216,11 @@ pub enum ParseInputError {
     InvalidSymbol(char),
     #[error("Missing start")]
     MissingStart,
+    #[error("The loop from the start is broken")]
+    BrokenLoop,
     #[error("Integer overflow: {0}")]
     Overflow(#[from] TryFromIntError),
-    #[error("{0:?}")]
+    #[error("{0}")]
     CommonError(#[from] CommonParseError),
 }
 
@@ -200,11 +229,10 @@ impl FromStr for Input {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut grid: Grid<Pipe> = s.parse()?;
-        let start = grid.position(|p| p == Pipe::S).unwrap();
-        if start == Pos::new(-1, -1) {
-            return Err(ParseInputError::MissingStart);
-        }
-        let start_pipe = match [Dir::N, Dir::E, Dir::S, Dir::W].map(|d| {
+        let start = grid
+            .position(|p| p == Pipe::S)
+            .ok_or(ParseInputError::MissingStart)?;
+        let start_pipe = match Dir::ALL.map(|d| {
             grid.get(start + d)
                 .is_some_and(|p| p.connected(d.reverse()))
         }) {
@@ -217,6 +245,10 @@ impl FromStr for Input {
             _ => return Err(ParseInputError::MissingStart),
         };
         grid.set(start, start_pipe);
-        Ok(Self { grid, start })
+        let input = Self { grid, start };
+        if !input.walk_loop(|_, _| ()) {
+            return Err(ParseInputError::BrokenLoop);
+        }
+        Ok(input)
     }
 }
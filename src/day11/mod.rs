@@ -1,43 +1,52 @@
 use std::str::FromStr;
 
-use bstr::ByteSlice;
 use thiserror::Error;
 
+use crate::aoclib::{grid_lines, CommonParseError};
+use crate::solution::Solution;
+
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 11");
-
-    println!("++Example");
-    let example = parse_input(EXAMPLE);
-    println!("|+-Part 1: {} (expected 374)", part_1(&example));
-    println!(
-        "|'-Part 2; 10: {} (expected 1_030)",
-        distance_between_galaxies(&example, 10)
-    );
-    println!(
-        "|'-Part 2; 100: {} (expected 8_410)",
-        distance_between_galaxies(&example, 100)
-    );
-
-    println!("++Input");
-    let input = parse_input(INPUT);
-    println!("|+-Part 1: {} (expected 9_724_940)", part_1(&input));
-    println!("|'-Part 2: {} (expected 569_052_586_852)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Real input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 11;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] = &[
+        ("example", EXAMPLE, 10),
+        ("example_100", EXAMPLE, 100),
+        ("input", INPUT, 1_000_000),
+    ];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = u64;
+    type Answer2 = u64;
+    /// Factor by which empty rows and columns expand in part 2
+    type Params = u64;
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _empty_scale: &u64) -> Option<u64> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, &empty_scale: &u64) -> Option<u64> {
+        Some(part_2(input, empty_scale))
+    }
 }
 
 #[must_use]
@@ -46,8 +55,8 @@ pub fn part_1(input: &Input) -> u64 {
 }
 
 #[must_use]
-pub fn part_2(input: &Input) -> u64 {
-    distance_between_galaxies(input, 1_000_000)
+pub fn part_2(input: &Input, empty_scale: u64) -> u64 {
+    distance_between_galaxies(input, empty_scale)
 }
 
 fn distance_between_galaxies(input: &Input, empty_scale: u64) -> u64 {
@@ -57,7 +66,7 @@ fn distance_between_galaxies(input: &Input, empty_scale: u64) -> u64 {
 }
 
 fn distance_1d(positions: &[usize], empty_scale: u64) -> u64 {
-    let mut pos_prev = positions[0];
+    let mut pos_prev = positions.first().copied().unwrap_or(0);
     let mut pos_short = 0;
     let mut pos_long = 0;
 
@@ -85,12 +94,10 @@ pub struct Input {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
-    #[error("Input is empty")]
-    EmptyInput,
-    #[error("Lines not same length")]
-    InvaidInput,
     #[error("Found invalid character: {0:?}")]
     InvalidChar(char),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 impl FromStr for Input {
@@ -99,25 +106,19 @@ impl FromStr for Input {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut galaxy_rows = Vec::with_capacity(450);
         let mut galaxy_cols = Vec::with_capacity(450);
-        let mut lines = s.as_bytes().lines().enumerate();
-        let first_line = lines.next().ok_or(ParseInputError::EmptyInput)?;
-        #[cfg(debug_assertions)]
-        let width = first_line.1.len();
-
-        for (r, srow) in [first_line].into_iter().chain(lines) {
-            #[cfg(debug_assertions)]
-            if srow.len() != width {
-                return Err(ParseInputError::InvaidInput);
-            }
-            let mut start = 0;
-            while let Some(c) = srow[start..].find_byte(b'#') {
-                #[cfg(debug_assertions)]
-                if !srow[start..start + c].bytes().all(|ch| ch == b'.') {
-                    return Err(ParseInputError::InvaidInput);
+        for (r, (offset, srow)) in grid_lines(s.as_bytes())?.into_iter().enumerate() {
+            for (c, &ch) in srow.iter().enumerate() {
+                match ch {
+                    b'#' => {
+                        galaxy_rows.push(r);
+                        galaxy_cols.push(c);
+                    }
+                    b'.' => (),
+                    _ => {
+                        let err = ParseInputError::InvalidChar(ch as char);
+                        return Err(CommonParseError::invalid_cell(err, offset + c));
+                    }
                 }
-                galaxy_rows.push(r);
-                galaxy_cols.push(start + c);
-                start += c + 1;
             }
         }
         galaxy_cols.sort_unstable();
@@ -127,7 +128,3 @@ impl FromStr for Input {
         })
     }
 }
-
-fn parse_input(text: &str) -> Input {
-    text.parse::<Input>().expect("Parse input")
-}
@@ -3,36 +3,47 @@ use num_traits::PrimInt;
 use smallvec::SmallVec;
 use thiserror::Error;
 
-use crate::aoclib::{parse_int, ParseIntError2};
+use crate::aoclib::{lines_with_offsets, parse_int, CommonParseError, ParseIntError2};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 12");
-
-    println!("++Example");
-    let example = parse_input(EXAMPLE).expect("Parse example");
-    println!("|+-Part 1: {} (expected 21)", part_1(&example));
-    println!("|'-Part 2: {} (expected 525 152)", part_2(&example));
-
-    println!("++Input");
-    let input = parse_input(INPUT).expect("Parse input");
-    println!("|+-Part 1: {} (expected 7 694)", part_1(&input));
-    println!("|'-Part 2: {} (expected 5 071 883 216 318)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Vec<Input> {
     parse_input(INPUT).expect("Real input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 12;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Vec<Input>;
+    type Transformed<'a> = Vec<Input>;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        parse_input(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -59,16 +70,32 @@ pub fn part_2(input: &[Input]) -> usize {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
     #[error("Invalid character: {0}")]
     InvalidInput(char),
+    #[error("Record has {0} springs, expected 1 to {MAX_LEN}")]
+    InvalidLength(usize),
+    #[error("Record has {0} groups, expected 1 to {MAX_GROUPS}")]
+    InvalidGroupCount(usize),
+    #[error("Group of {0} springs does not fit in the record")]
+    InvalidGroup(usize),
     #[error("Expected character: {0}")]
     Expected(char),
-    #[error(transparent)]
+    #[error("{0}")]
     InvalidNumber(#[from] ParseIntError2),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
+/// Longest record supported. The caches of the parts are sized for it, and an unfolded record
+/// must fit in a `u128`.
+const MAX_LEN: usize = 20;
+/// Most groups supported in a record
+const MAX_GROUPS: usize = 6;
+
 #[derive(Debug, Clone)]
-pub struct Input<T = u32, const N: usize = 6> {
+pub struct Input<T = u32, const N: usize = MAX_GROUPS> {
     len: usize,
     mask: T,
     broken: T,
@@ -124,13 +151,19 @@ where
         let mut broken = zero;
         let mut counts = SmallVec::new();
         let len = line.find_byte(b' ').ok_or(ParseInputError::Expected(' '))?;
+        if !(1..=MAX_LEN).contains(&len) {
+            return Err(ParseInputError::InvalidLength(len));
+        }
         let mut shift = len - 1;
-        for &b in &line[..len] {
+        for (col, &b) in line[..len].iter().enumerate() {
             let (m, b) = match b {
                 b'.' => (one, zero),
                 b'#' => (one, one),
                 b'?' => (zero, zero),
-                b => return Err(ParseInputError::InvalidInput(b as char)),
+                b => {
+                    let err = ParseInputError::InvalidInput(b as char);
+                    return Err(CommonParseError::invalid_cell(err, col));
+                }
             };
             mask = mask | (m << shift);
             broken = broken | (b << shift);
@@ -139,13 +172,20 @@ where
 
         let mut start = len + 1;
         while let Some(ix) = line[start..].find_byte(b',') {
-            let num: usize = parse_int(&line[start..start + ix])?;
+            let num: usize =
+                parse_int(&line[start..start + ix]).map_err(|err| err.offset_by(start))?;
             counts.push(num);
             start += ix + 1;
         }
 
-        let num: usize = parse_int(&line[start..])?;
+        let num: usize = parse_int(&line[start..]).map_err(|err| err.offset_by(start))?;
         counts.push(num);
+        if counts.len() > MAX_GROUPS {
+            return Err(ParseInputError::InvalidGroupCount(counts.len()));
+        }
+        if let Some(&count) = counts.iter().find(|&&count| count == 0 || count > len) {
+            return Err(ParseInputError::InvalidGroup(count));
+        }
 
         Ok(Self {
             len,
@@ -235,8 +275,14 @@ where
 
 fn parse_input(text: &str) -> Result<Vec<Input>, ParseInputError> {
     let mut res: Vec<Input> = Vec::with_capacity(1000);
-    for line in text.as_bytes().lines() {
-        res.push(line.try_into()?);
+    for (offset, line) in lines_with_offsets(text.as_bytes()) {
+        let input = line
+            .try_into()
+            .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+        res.push(input);
+    }
+    if res.is_empty() {
+        return Err(ParseInputError::EmptyInput);
     }
     Ok(res)
 }
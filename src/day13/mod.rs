@@ -1,47 +1,58 @@
 use std::str::FromStr;
 
-use bstr::ByteSlice;
-use smallvec::SmallVec;
 use thiserror::Error;
 
+use crate::aoclib::{grid_lines, lines_with_offsets, BitGrid, CommonParseError};
+use crate::solution::Solution;
+
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 13");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1: {} (expected 405)", part_1(&example));
-    println!("|'-Part 2: {} (expected 400)", part_2(&example));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 35232)", part_1(&input));
-    println!("|'-Part 2: {} (expected 37982)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 13;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
 pub fn part_1(input: &Input) -> usize {
     let mut sum = 0;
     for item in &input.patterns {
-        if let Some(r) = find_mirror_with_smudges::<0>(&item.row_masks) {
+        if let Some(r) = find_mirror_with_smudges::<0>(&item.rows) {
             sum += 100 * r;
         }
-        if let Some(c) = find_mirror_with_smudges::<0>(&item.col_masks) {
+        if let Some(c) = find_mirror_with_smudges::<0>(&item.cols) {
             sum += c;
         }
     }
@@ -52,22 +63,26 @@ pub fn part_1(input: &Input) -> usize {
 pub fn part_2(input: &Input) -> usize {
     let mut sum = 0;
     for item in &input.patterns {
-        if let Some(r) = find_mirror_with_smudges::<1>(&item.row_masks) {
+        if let Some(r) = find_mirror_with_smudges::<1>(&item.rows) {
             sum += 100 * r;
         }
-        if let Some(c) = find_mirror_with_smudges::<1>(&item.col_masks) {
+        if let Some(c) = find_mirror_with_smudges::<1>(&item.cols) {
             sum += c;
         }
     }
     sum
 }
 
-fn find_mirror_with_smudges<const N: u32>(masks: &[u32]) -> Option<usize> {
-    let len = masks.len();
+/// The number of rows above a mirror between two rows of `grid`, where the reflected rows differ
+/// in exactly `N` cells
+fn find_mirror_with_smudges<const N: u32>(grid: &BitGrid) -> Option<usize> {
+    let len = grid.height();
     'outer: for start_ix in 1..len {
         let mut diffs = 0;
-        for (&above, &below) in masks[..start_ix].iter().rev().zip(&masks[start_ix..]) {
-            diffs += (above ^ below).count_ones();
+        for (above, below) in (0..start_ix).rev().zip(start_ix..len) {
+            for (&above, &below) in grid.row(above).iter().zip(grid.row(below)) {
+                diffs += (above ^ below).count_ones();
+            }
             if diffs > N {
                 continue 'outer;
             }
@@ -79,10 +94,37 @@ fn find_mirror_with_smudges<const N: u32>(masks: &[u32]) -> Option<usize> {
     None
 }
 
+/// A pattern, with the rocks in `cols` transposed so both directions can be compared by rows
 #[derive(Debug)]
 struct Pattern {
-    row_masks: SmallVec<[u32; 20]>,
-    col_masks: SmallVec<[u32; 20]>,
+    rows: BitGrid,
+    cols: BitGrid,
+}
+
+impl Pattern {
+    fn parse(text: &[u8]) -> Result<Self, ParseInputError> {
+        let lines = grid_lines(text)?;
+        let width = lines[0].1.len();
+        let height = lines.len();
+        let mut rows = BitGrid::new(width, height);
+        let mut cols = BitGrid::new(height, width);
+        for (r, (offset, line)) in lines.into_iter().enumerate() {
+            for (c, &ch) in line.iter().enumerate() {
+                match ch {
+                    b'.' => (),
+                    b'#' => {
+                        rows.set_range(r, c..=c, true);
+                        cols.set_range(c, r..=r, true);
+                    }
+                    ch => {
+                        let err = ParseInputError::InvalidChar(ch as char);
+                        return Err(CommonParseError::invalid_cell(err, offset + c));
+                    }
+                }
+            }
+        }
+        Ok(Self { rows, cols })
+    }
 }
 
 pub struct Input {
@@ -91,84 +133,36 @@ pub struct Input {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
     #[error("Unexpected character: '{0}'")]
     InvalidChar(char),
-    #[error("Uneven row lengths; Expected {0} got {1}")]
-    UnevenRows(usize, usize),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 impl FromStr for Input {
     type Err = ParseInputError;
 
     fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.as_bytes();
         let mut patterns: Vec<Pattern> = Vec::with_capacity(100);
-        let mut parser = PatternParser::new();
-        for line in text.as_bytes().lines() {
+        let mut pattern_start = None;
+        // An empty line after the last pattern ends it like the ones between patterns.
+        let lines = lines_with_offsets(text).chain([(text.len(), &[][..])]);
+        for (offset, line) in lines {
             if !line.is_empty() {
-                parser.parse_line(line)?;
-            } else if !parser.is_empty() {
-                // Empty line between patterns.
-                patterns.push(parser.complete());
+                pattern_start.get_or_insert(offset);
+            } else if let Some(start) = pattern_start.take() {
+                let pattern = Pattern::parse(&text[start..offset])
+                    .map_err(|err| CommonParseError::invalid_part(err, start))?;
+                patterns.push(pattern);
             }
         }
 
-        if !parser.is_empty() {
-            // Last pattern
-            patterns.push(parser.complete());
+        if patterns.is_empty() {
+            return Err(ParseInputError::EmptyInput);
         }
-
         Ok(Self { patterns })
     }
 }
-
-#[derive(Default)]
-struct PatternParser {
-    row_masks: SmallVec<[u32; 20]>,
-    col_masks: SmallVec<[u32; 20]>,
-    row: usize,
-    width: usize,
-}
-
-impl PatternParser {
-    pub fn new() -> Self {
-        PatternParser::default()
-    }
-
-    pub fn parse_line(&mut self, line: &[u8]) -> Result<(), ParseInputError> {
-        if self.row == 0 {
-            self.width = line.len();
-            self.col_masks.extend(std::iter::repeat(0).take(self.width));
-        } else if line.len() != self.width {
-            return Err(ParseInputError::UnevenRows(self.width, line.len()));
-        }
-
-        let mut row_mask = 0_u32;
-        for (col, ch) in line.bytes().enumerate() {
-            match ch {
-                b'.' => (),
-                b'#' => {
-                    row_mask |= 1_u32 << col;
-                    self.col_masks[col] |= 1_u32 << self.row;
-                }
-                ch => return Err(ParseInputError::InvalidChar(ch as char)),
-            }
-        }
-        self.row_masks.push(row_mask);
-        self.row += 1;
-        Ok(())
-    }
-
-    pub fn is_empty(&self) -> bool {
-        self.row == 0
-    }
-
-    pub fn complete(&mut self) -> Pattern {
-        let row_masks = std::mem::take(&mut self.row_masks);
-        let col_masks = std::mem::take(&mut self.col_masks);
-        self.row = 0;
-        Pattern {
-            row_masks,
-            col_masks,
-        }
-    }
-}
@@ -1,36 +1,48 @@
-use bstr::ByteSlice;
 use std::fmt::Display;
 use std::str::FromStr;
 use thiserror::Error;
 
+use crate::aoclib::{grid_lines, BitGrid, CommonParseError, Cycle, Pos};
+use crate::solution::Solution;
+
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 14");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1: {} (expected 136)", part_1(&example));
-    println!("|'-Part 2: {} (expected 64)", part_2(&example));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 113_486)", part_1(&input));
-    println!("|'-Part 2: {} (expected 104_409)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 14;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -40,161 +52,106 @@ pub fn part_1(input: &Input) -> usize {
     input.north_load()
 }
 
-const HASH_MODULO: usize = 1697;
-const HASH_FACTOR: usize = 17;
-
 #[must_use]
 pub fn part_2(input: &Input) -> usize {
-    let mut input = input.clone();
-    let mut seen = [None; HASH_MODULO];
-    let mut step = 0;
-    let repeat = loop {
-        let hash = input.tilt_cycle();
-        step += 1;
-        if let Some(repeat) = seen[hash] {
-            break repeat;
-        }
-        seen[hash] = Some(step);
-    };
-    let cycle = step - repeat;
-    let remaining_cycles = (1_000_000_000 - step) % cycle;
-    for _ in 0..remaining_cycles {
-        input.tilt_cycle();
-        step += 1;
-    }
-    input.north_load()
+    // The fixed rocks never move, so only the rolling rocks tell the states apart
+    let cycle = Cycle::find_by_key(input.clone(), Input::tilt_cycle, |input| {
+        input.rocks.clone()
+    });
+    cycle.nth(1_000_000_000).north_load()
 }
 
-#[derive(Debug, Clone)]
-struct Row {
-    fixed: u128,
-    rocks: u128,
-}
-
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Input {
     width: usize,
     height: usize,
-    rows: Vec<Row>,
+    fixed: BitGrid,
+    rocks: BitGrid,
 }
 
 impl Input {
     fn north_load(&self) -> usize {
         let mut sum = 0;
-        for (r, row) in self.rows.iter().enumerate() {
-            let count = row.rocks.count_ones() as usize;
+        for r in 0..self.height {
+            let count = self.rocks.count_ones_in(r, ..);
             sum += count * (self.height - r);
         }
         sum
     }
 
-    fn tilt_cycle(&mut self) -> usize {
+    fn tilt_cycle(&mut self) {
         self.tilt_north();
         self.tilt_west();
         self.tilt_south();
-        self.tilt_east()
+        self.tilt_east();
     }
 
+    /// The rows above have already settled, so the rocks of each row move up one row at a time,
+    /// 64 columns at once, until every one of them is blocked.
     fn tilt_north(&mut self) {
-        for c in 0..self.width {
-            let bit = 1_u128 << c;
-            let mut w = 0;
-            for r in 0..self.height {
-                if (self.rows[r].fixed & bit) != 0 {
-                    w = r + 1;
-                }
-                if (self.rows[r].rocks & bit) != 0 {
-                    self.rows[r].rocks &= !bit;
-                    self.rows[w].rocks |= bit;
-                    w += 1;
-                }
-            }
+        for row in 1..self.height {
+            self.roll(row, 0);
         }
     }
 
     fn tilt_south(&mut self) {
-        for c in 0..self.width {
-            let bit = 1_u128 << c;
-            let mut w = self.height - 1;
-            for r in (0..self.height).rev() {
-                if (self.rows[r].fixed & bit) != 0 {
-                    w = r.saturating_sub(1);
-                }
-                if (self.rows[r].rocks & bit) != 0 {
-                    self.rows[r].rocks &= !bit;
-                    self.rows[w].rocks |= bit;
-                    w = w.saturating_sub(1);
-                }
-            }
+        for row in (0..self.height - 1).rev() {
+            self.roll(row, self.height - 1);
         }
     }
 
-    fn tilt_west(&mut self) {
-        for row in &mut self.rows {
-            let rocks = &mut row.rocks;
-            let mut fixed = row.fixed;
-            let mut last_fixed_bit = 1_u128;
-            while fixed != 0 {
-                let fixed_bit = 1_u128 << fixed.trailing_zeros();
-                let mask = fixed_bit - last_fixed_bit;
-                let num_rocks = (*rocks & mask).count_ones();
-                *rocks = *rocks & !mask | ((last_fixed_bit << num_rocks) - last_fixed_bit);
-                fixed &= !fixed_bit;
-                last_fixed_bit = fixed_bit << 1;
+    /// Move the rocks of `row` one row at a time towards the row `edge`, as long as they are not
+    /// blocked
+    fn roll(&mut self, row: usize, edge: usize) {
+        for ix in 0..self.rocks.row(row).len() {
+            let mut moving = self.rocks.row(row)[ix];
+            let mut from = row;
+            while from != edge {
+                let to = if edge < from { from - 1 } else { from + 1 };
+                moving &= !(self.rocks.row(to)[ix] | self.fixed.row(to)[ix]);
+                if moving == 0 {
+                    break;
+                }
+                self.rocks.row_mut(from)[ix] &= !moving;
+                self.rocks.row_mut(to)[ix] |= moving;
+                from = to;
             }
-            let fixed_bit = 1_u128 << self.width;
-            let mask = fixed_bit - last_fixed_bit;
-            let num_rocks = (*rocks & mask).count_ones();
-            *rocks = *rocks & !mask | ((last_fixed_bit << num_rocks) - last_fixed_bit);
         }
     }
 
-    fn tilt_east(&mut self) -> usize {
-        let mut hash = 0;
-        for row in &mut self.rows {
-            let rocks = &mut row.rocks;
-            let mut fixed = row.fixed;
-            let mut last_fixed_bit = 1_u128;
-            while fixed != 0 {
-                let fixed_bit = 1_u128 << fixed.trailing_zeros();
-                let mask = fixed_bit - last_fixed_bit;
-                let num_rocks = (*rocks & mask).count_ones();
-                *rocks = *rocks & !mask | (fixed_bit - (fixed_bit >> num_rocks));
-                fixed &= !fixed_bit;
-                last_fixed_bit = fixed_bit;
-                hash = (hash * HASH_FACTOR + num_rocks as usize) % HASH_MODULO;
+    fn tilt_west(&mut self) {
+        for r in 0..self.height {
+            let mut start = 0;
+            for end in self.fixed.iter_ones(r).chain([self.width]) {
+                let num_rocks = self.rocks.count_ones_in(r, start..end);
+                self.rocks.set_range(r, start..end, false);
+                self.rocks.set_range(r, start..start + num_rocks, true);
+                start = end + 1;
             }
-            let fixed_bit = 1_u128 << self.width;
-            let mask = fixed_bit - last_fixed_bit;
-            let num_rocks = (*rocks & mask).count_ones();
-            *rocks = *rocks & !mask | (fixed_bit - (fixed_bit >> num_rocks));
-            hash = (hash * HASH_FACTOR + num_rocks as usize) % HASH_MODULO;
         }
-        hash
     }
 
-    fn parse_line(line: &[u8]) -> Result<Row, ParseInputError> {
-        let mut row = Row { fixed: 0, rocks: 0 };
-        for (i, ch) in line.bytes().enumerate() {
-            match ch {
-                b'#' => row.fixed |= 1_u128 << i,
-                b'O' => row.rocks |= 1_u128 << i,
-                b'.' => (),
-                ch => return Err(ParseInputError::InvalidChar(ch as char)),
-            };
+    fn tilt_east(&mut self) {
+        for r in 0..self.height {
+            let mut start = 0;
+            for end in self.fixed.iter_ones(r).chain([self.width]) {
+                let num_rocks = self.rocks.count_ones_in(r, start..end);
+                self.rocks.set_range(r, start..end, false);
+                self.rocks.set_range(r, end - num_rocks..end, true);
+                start = end + 1;
+            }
         }
-        Ok(row)
     }
 }
 
 impl Display for Input {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for row in &self.rows {
+        for r in 0..self.height {
             for c in 0..self.width {
-                let bit = 1_u128 << c;
-                if (row.fixed & bit) != 0 {
+                let pos = Pos::new(isize::try_from(r).unwrap(), isize::try_from(c).unwrap());
+                if self.fixed.get(pos) {
                     write!(f, "#")?;
-                } else if (row.rocks & bit) != 0 {
+                } else if self.rocks.get(pos) {
                     write!(f, "O")?;
                 } else {
                     write!(f, ".")?;
@@ -208,35 +165,39 @@ impl Display for Input {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
-    #[error("Input is empty")]
-    EmptyInput,
-    #[error("Rows of input is uneven")]
-    UnevenRows,
     #[error("Unexpected character: '{0}'")]
     InvalidChar(char),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 impl FromStr for Input {
     type Err = ParseInputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let mut lines = s.as_bytes().lines();
-        let first_line = lines.next().ok_or(ParseInputError::EmptyInput)?;
-        let width = first_line.len();
-        let mut rows = Vec::with_capacity(width);
-        for line in [first_line].into_iter().chain(lines) {
-            #[cfg(debug_assertions)]
-            if line.len() != width {
-                return Err(ParseInputError::UnevenRows);
+        let lines = grid_lines(s.as_bytes())?;
+        let width = lines[0].1.len();
+        let height = lines.len();
+        let mut fixed = BitGrid::new(width, height);
+        let mut rocks = BitGrid::new(width, height);
+        for (r, (offset, line)) in lines.into_iter().enumerate() {
+            for (c, &ch) in line.iter().enumerate() {
+                match ch {
+                    b'#' => fixed.set_range(r, c..=c, true),
+                    b'O' => rocks.set_range(r, c..=c, true),
+                    b'.' => (),
+                    ch => {
+                        let err = ParseInputError::InvalidChar(ch as char);
+                        return Err(CommonParseError::invalid_cell(err, offset + c));
+                    }
+                };
             }
-            let row = Self::parse_line(line)?;
-            rows.push(row);
         }
-        let height = rows.len();
         Ok(Self {
             width,
             height,
-            rows,
+            fixed,
+            rocks,
         })
     }
 }
@@ -3,34 +3,47 @@ use std::str::FromStr;
 use smallvec::{smallvec, SmallVec};
 use thiserror::Error;
 
+use crate::aoclib::CommonParseError;
+use crate::solution::Solution;
+
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 15");
-
-    println!("++Example");
-    let example = Input::from_str(EXAMPLE).expect("Parse example");
-    println!("|+-Part 1: {} (expected 1320)", part_1(&example));
-    println!("|'-Part 2: {} (expected 145)", part_2(&example));
-
-    println!("++Input");
-    let input = Input::from_str(INPUT).expect("Parse input");
-    println!("|+-Part 1: {} (expected 508498)", part_1(&input));
-    println!("|'-Part 2: {} (expected 279116)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 15;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -129,7 +142,9 @@ impl TryFrom<&[u8]> for Step {
     fn try_from(b: &[u8]) -> Result<Self, Self::Error> {
         let (prefix, operation) = match b {
             [prefix @ .., b'-'] => (prefix, Operation::Remove),
-            [prefix @ .., b'=', digit] => (prefix, Operation::Insert(digit - b'0')),
+            [prefix @ .., b'=', digit] if digit.is_ascii_digit() => {
+                (prefix, Operation::Insert(digit - b'0'))
+            }
             _ => return Err(ParseInputError::MissingOperation),
         };
         let step = Self {
@@ -159,6 +174,8 @@ impl Input {
 pub enum ParseInputError {
     #[error("Missing operation indicator")]
     MissingOperation,
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 impl FromStr for Input {
@@ -166,8 +183,13 @@ impl FromStr for Input {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut steps = Vec::with_capacity(4000);
+        let mut offset = 0;
         for description in s.as_bytes().split(|&ch| ch == b',') {
-            steps.push(description.try_into()?);
+            let step = description
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+            steps.push(step);
+            offset += description.len() + 1;
         }
         Ok(Self::new(steps))
     }
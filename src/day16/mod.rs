@@ -4,36 +4,47 @@ use std::ops::{Index, IndexMut};
 use std::str::FromStr;
 use thiserror::Error;
 
-use crate::aoclib::Dir;
+use crate::aoclib::{grid_lines, BitGrid, CommonParseError, Dir};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 16");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1: {} (expected 46)", part_1(&example));
-    println!("|'-Part 2: {} (expected 51)", part_2(&example));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 6605)", part_1(&input));
-    println!("|'-Part 2: {} (expected 6766)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> MirrorGraph {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 16;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = MirrorGraph;
+    type Transformed<'a> = MirrorGraph;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -64,7 +75,7 @@ impl Debug for DirMap<bool> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut last = None;
         let mut multiple = false;
-        for d in [Dir::N, Dir::E, Dir::S, Dir::W] {
+        for d in Dir::ALL {
             if self[d] {
                 if last.is_none() {
                     last = Some(d);
@@ -86,7 +97,7 @@ impl Debug for DirMap<bool> {
 impl<T: Debug + Copy> Debug for DirMap<Option<T>> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut first = false;
-        for d in [Dir::N, Dir::E, Dir::S, Dir::W] {
+        for d in Dir::ALL {
             if let Some(v) = self[d] {
                 if first {
                     write!(f, "[{d:?}: {v:?}")?;
@@ -108,26 +119,26 @@ impl<T> Index<Dir> for DirMap<T> {
     type Output = T;
 
     fn index(&self, index: Dir) -> &Self::Output {
-        &self.0[index as usize]
+        &self.0[index.index()]
     }
 }
 
 impl<T> IndexMut<Dir> for DirMap<T> {
     fn index_mut(&mut self, index: Dir) -> &mut Self::Output {
-        &mut self.0[index as usize]
+        &mut self.0[index.index()]
     }
 }
 
 struct LaserShooter<'a> {
     graph: &'a MirrorGraph,
-    visited_tiles: Vec<u128>,
+    visited_tiles: BitGrid,
     visited_nodes: Vec<DirMap<bool>>,
     pending: Vec<(usize, Dir)>,
 }
 
 impl<'a> LaserShooter<'a> {
     pub fn new(graph: &'a MirrorGraph) -> Self {
-        let visited_tiles: Vec<u128> = vec![0; graph.height];
+        let visited_tiles = BitGrid::new(graph.width, graph.height);
         let visited_nodes: Vec<DirMap<bool>> = vec![DirMap::default(); graph.nodes.len()];
         let pending: Vec<(usize, Dir)> = Vec::with_capacity(64);
         Self {
@@ -139,9 +150,7 @@ impl<'a> LaserShooter<'a> {
     }
 
     pub fn shoot_laser(&mut self, row: usize, col: usize, dir: Dir) -> usize {
-        for vis in &mut self.visited_tiles {
-            *vis = 0;
-        }
+        self.visited_tiles.clear();
         for dirmap in &mut self.visited_nodes {
             *dirmap = DirMap::default();
         }
@@ -170,26 +179,18 @@ impl<'a> LaserShooter<'a> {
                 }
             }
         }
-        let mut sum = 0;
-        for &vis in &self.visited_tiles {
-            sum += vis.count_ones();
-        }
-        sum as usize
+        self.visited_tiles.count_ones()
     }
 
     #[allow(clippy::cast_sign_loss)]
     fn incoming_beam(&mut self, row: usize, col: usize, dir: Dir) -> Result<(usize, Dir), usize> {
-        let bitfield = Bitfield::new(self.graph.width);
         let start_node_ix = match dir {
             Dir::N => {
-                let bit = bitfield.bit(col);
                 let Some(node_ix) = self.graph.from_south[col] else {
                     return Err(self.graph.height);
                 };
                 let row = self.graph.nodes[node_ix].row;
-                for vis in &mut self.visited_tiles[row..] {
-                    *vis |= bit;
-                }
+                self.visited_tiles.set_column_range(col, row.., true);
                 node_ix
             }
             Dir::E => {
@@ -197,18 +198,15 @@ impl<'a> LaserShooter<'a> {
                     return Err(self.graph.width);
                 };
                 let col = self.graph.nodes[node_ix].col;
-                self.visited_tiles[row] |= bitfield.left_of(col);
+                self.visited_tiles.set_range(row, ..=col, true);
                 node_ix
             }
             Dir::S => {
-                let bit = bitfield.bit(col);
                 let Some(node_ix) = self.graph.from_north[col] else {
                     return Err(self.graph.height);
                 };
                 let row = self.graph.nodes[node_ix].row;
-                for vis in &mut self.visited_tiles[..=row] {
-                    *vis |= bit;
-                }
+                self.visited_tiles.set_column_range(col, ..=row, true);
                 node_ix
             }
             Dir::W => {
@@ -216,7 +214,7 @@ impl<'a> LaserShooter<'a> {
                     return Err(self.graph.width);
                 };
                 let col = self.graph.nodes[node_ix].col;
-                self.visited_tiles[row] |= bitfield.right_of(col);
+                self.visited_tiles.set_range(row, col.., true);
                 node_ix
             }
         };
@@ -224,95 +222,40 @@ impl<'a> LaserShooter<'a> {
     }
 
     fn outgoing_beam(&mut self, node: &Node, dir: Dir) {
-        let bitfield = Bitfield::new(self.graph.width);
         match dir {
             Dir::N => {
-                let bit = bitfield.bit(node.col);
-                for vis in &mut self.visited_tiles[..=node.row] {
-                    *vis |= bit;
-                }
+                self.visited_tiles
+                    .set_column_range(node.col, ..=node.row, true);
             }
             Dir::E => {
-                self.visited_tiles[node.row] |= bitfield.right_of(node.col);
+                self.visited_tiles.set_range(node.row, node.col.., true);
             }
             Dir::S => {
-                let bit = bitfield.bit(node.col);
-                for vis in &mut self.visited_tiles[node.row..] {
-                    *vis |= bit;
-                }
+                self.visited_tiles
+                    .set_column_range(node.col, node.row.., true);
             }
             Dir::W => {
-                self.visited_tiles[node.row] |= bitfield.left_of(node.col);
+                self.visited_tiles.set_range(node.row, ..=node.col, true);
             }
         }
     }
 
     fn between_beam(&mut self, node1: &Node, node2: &Node) {
-        let bitfield = Bitfield::new(self.graph.width);
         if node2.row == node1.row {
             // Horizontal
-            self.visited_tiles[node1.row] |= bitfield.between(node1.col, node2.col);
+            let col1 = node1.col.min(node2.col);
+            let col2 = node1.col.max(node2.col);
+            self.visited_tiles.set_range(node1.row, col1..=col2, true);
         } else {
             // Vertical
             let row1 = node1.row.min(node2.row);
             let row2 = node1.row.max(node2.row);
-            let bit = bitfield.bit(node1.col);
-            for vis in &mut self.visited_tiles[row1..=row2] {
-                *vis |= bit;
-            }
+            self.visited_tiles
+                .set_column_range(node1.col, row1..=row2, true);
         }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Bitfield {
-    width: usize,
-}
-
-impl Bitfield {
-    fn new(width: usize) -> Self {
-        Self { width }
-    }
-}
-impl Bitfield {
-    // col = 0 => bit = width - 1 (MSB) -- "leftmost"
-    // col = width - 1 => bit = 0 (LSB) -- "rightmost"
-    pub fn left_of(self, col: usize) -> u128 {
-        // inclusive
-        // 11110000 <-- LSB
-        // '------' width = 8
-        // '--' col = 3
-        !(!0_u128 << (col + 1)) << (self.width - col - 1)
-    }
-    pub fn right_of(self, col: usize) -> u128 {
-        // inclusive
-        // 00011111 <-- LSB
-        // '------' width = 8
-        // '--' col = 3
-        !(!0_u128 << (self.width - col))
-    }
-    pub fn between(self, col1: usize, col2: usize) -> u128 {
-        // inclusive
-        // 00111000 <-- LSB
-        // '------' width = 8
-        // '---' col2 = 4
-        // '-' col1 = 2
-        // 00111111 right_of(2)
-        // 00001111 right_of(4)
-        // 00110000 ^
-        // 00101000 bit(2) | bit(4)
-        // 00111000
-        (self.right_of(col1) ^ self.right_of(col2)) | self.bit(col1) | self.bit(col2)
-    }
-    pub fn bit(self, col: usize) -> u128 {
-        let _ = self;
-        // 00010000 <-- LSB
-        // '------' width = 8
-        // '--' col = 3
-        1_u128 << (self.width - col - 1)
-    }
-}
-
 #[derive(Debug, Clone)]
 struct Node {
     row: usize,
@@ -347,17 +290,20 @@ impl FromStr for MirrorGraph {
     type Err = ParseInputError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let width = s.lines().next().unwrap().len();
+        let lines = grid_lines(s.as_bytes())?;
+        let width = lines[0].1.len();
         let mut nodes: Vec<Node> = Vec::with_capacity(1238);
         let mut from_north = vec![None; width];
         let mut from_south = vec![None; width];
         let mut from_west = Vec::with_capacity(width);
         let mut from_east = Vec::with_capacity(width);
-        for (r, line) in s.lines().enumerate() {
+        for (r, (offset, line)) in lines.into_iter().enumerate() {
             let mut from_west_r: Option<usize> = None;
             let mut from_east_r: Option<usize> = None;
-            for (c, ch) in line.bytes().enumerate() {
-                let tile: Tile = ch.try_into()?;
+            for (c, &ch) in line.iter().enumerate() {
+                let tile: Tile = ch
+                    .try_into()
+                    .map_err(|err| CommonParseError::invalid_cell(err, offset + c))?;
                 if tile.is_empty() {
                     continue;
                 }
@@ -449,4 +395,6 @@ impl TryFrom<u8> for Tile {
 pub enum ParseInputError {
     #[error("Unexpected character: '{0}'")]
     InvalidChar(char),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
@@ -4,41 +4,51 @@ use std::hash::Hash;
 use std::str::FromStr;
 use thiserror::Error;
 
-use crate::aoclib::{CommonParseError, Dir, Grid, Pos};
+use crate::aoclib::{CellByte, CommonParseError, Dir, Grid, Pos};
+use crate::solution::Solution;
 
 const EXAMPLE1: &str = include_str!("example1.txt");
 const EXAMPLE2: &str = include_str!("example2.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 17");
-
-    println!("++Example 1");
-    let example = EXAMPLE1.parse().expect("Parse example 1");
-    println!("|+-Part 1: {} (expected 102)", part_1(&example));
-    println!("|'-Part 2: {} (expected 94)", part_2(&example));
-
-    println!("++Example 2");
-    let example = EXAMPLE2.parse().expect("Parse example 2");
-    println!("|'-Part 2: {} (expected 71)", part_2(&example));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 1099)", part_1(&input));
-    println!("|'-Part 2: {} (expected 1266)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 17;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] = &[
+        ("example1", EXAMPLE1, ()),
+        ("example2", EXAMPLE2, ()),
+        ("input", INPUT, ()),
+    ];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -147,11 +157,17 @@ impl TryFrom<u8> for Cell {
     fn try_from(value: u8) -> Result<Self, Self::Error> {
         value
             .is_ascii_digit()
-            .then_some(Self(value - b'0'))
+            .then(|| Self(value - b'0'))
             .ok_or(ParseInputError::InvalidChar(value as char))
     }
 }
 
+impl CellByte for Cell {
+    fn to_byte(self) -> u8 {
+        b'0' + self.0
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Input {
     grid: Grid<Cell>,
@@ -161,7 +177,7 @@ pub struct Input {
 pub enum ParseInputError {
     #[error("Unexpected character: '{0}'")]
     InvalidChar(char),
-    #[error("{0:?}")]
+    #[error("{0}")]
     CommonError(#[from] CommonParseError),
 }
 
@@ -1,41 +1,51 @@
-use bstr::ByteSlice;
+use bstr::{ByteSlice, Bytes};
 use std::fmt::Debug;
 use std::str::FromStr;
 use thiserror::Error;
 
-use crate::aoclib::{Dir, MultiDir, Pos};
+use crate::aoclib::{
+    lines_with_offsets, parse_int_radix, CommonParseError, Dir, MultiDir, ParseIntError2, Pos,
+};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 18");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1: {} (expected 62)", part_1(&example));
-    println!("|'-Part 2: {} (expected 952 408 144 115)", part_2(&example));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 47 139)", part_1(&input));
-    println!(
-        "|'-Part 2: {} (expected 173 152 345 887 206)",
-        part_2(&input)
-    );
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 18;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = i64;
+    type Answer2 = i64;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<i64> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<i64> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -75,32 +85,21 @@ impl<'a> TryFrom<&'a [u8]> for Instruction {
     type Error = ParseInputError;
 
     fn try_from(s: &[u8]) -> Result<Self, Self::Error> {
-        fn expect(
-            bs: &mut impl Iterator<Item = u8>,
-            check: fn(&u8) -> bool,
-        ) -> Result<u8, ParseInputError> {
+        let mut bs = s.bytes();
+        // The unexpected character just taken from `bs`
+        let invalid = |bs: &Bytes, ch: u8| {
+            let offset = s.len() - bs.as_bytes().len() - 1;
+            CommonParseError::invalid_cell(ParseInputError::InvalidChar(ch as char), offset)
+        };
+        let expect = |bs: &mut Bytes, check: fn(&u8) -> bool| -> Result<u8, ParseInputError> {
             match bs.next().ok_or(ParseInputError::EmptyInput)? {
                 ch if check(&ch) => Ok(ch),
-                ch => Err(ParseInputError::InvalidChar(ch as char)),
+                ch => Err(invalid(bs, ch)),
             }
-        }
-        fn expect_hex_digit(bs: &mut impl Iterator<Item = u8>) -> Result<usize, ParseInputError> {
-            Ok(match bs.next().ok_or(ParseInputError::EmptyInput)? {
-                ch if ch.is_ascii_digit() => (ch - b'0') as _,
-                ch @ b'A'..=b'F' => (ch - b'A' + 10) as _,
-                ch @ b'a'..=b'f' => (ch - b'a' + 10) as _,
-                ch => return Err(ParseInputError::InvalidChar(ch as char)),
-            })
-        }
-
-        let mut bs = s.bytes();
-        let dir = match bs.next().ok_or(ParseInputError::EmptyInput)? {
-            b'L' => Dir::W,
-            b'U' => Dir::N,
-            b'R' => Dir::E,
-            b'D' => Dir::S,
-            ch => return Err(ParseInputError::InvalidChar(ch as char)),
         };
+
+        let ch = bs.next().ok_or(ParseInputError::EmptyInput)?;
+        let dir = Dir::from_udlr(ch).ok_or_else(|| invalid(&bs, ch))?;
         expect(&mut bs, u8::is_ascii_whitespace)?;
         let mut dist = (expect(&mut bs, u8::is_ascii_digit)? - b'0') as usize;
         dist = match bs.next().ok_or(ParseInputError::EmptyInput)? {
@@ -109,21 +108,20 @@ impl<'a> TryFrom<&'a [u8]> for Instruction {
                 dist * 10 + (d - b'0') as usize
             }
             w if w.is_ascii_whitespace() => dist,
-            ch => return Err(ParseInputError::InvalidChar(ch as char)),
+            ch => return Err(invalid(&bs, ch)),
         };
         expect(&mut bs, |&ch| ch == b'(')?;
         expect(&mut bs, |&ch| ch == b'#')?;
-        let mut alt_distance = expect_hex_digit(&mut bs)? << 16;
-        alt_distance |= expect_hex_digit(&mut bs)? << 12;
-        alt_distance |= expect_hex_digit(&mut bs)? << 8;
-        alt_distance |= expect_hex_digit(&mut bs)? << 4;
-        alt_distance |= expect_hex_digit(&mut bs)?;
+        let hex = bs.as_bytes().get(..5).ok_or(ParseInputError::EmptyInput)?;
+        let hex_offset = s.len() - bs.as_bytes().len();
+        let alt_distance = parse_int_radix(hex, 16).map_err(|err| err.offset_by(hex_offset))?;
+        bs.nth(hex.len() - 1);
         let alt_direction = match bs.next().ok_or(ParseInputError::EmptyInput)? {
             b'0' => Dir::E,
             b'1' => Dir::S,
             b'2' => Dir::W,
             b'3' => Dir::N,
-            ch => return Err(ParseInputError::InvalidChar(ch as char)),
+            ch => return Err(invalid(&bs, ch)),
         };
         expect(&mut bs, |&ch| ch == b')')?;
 
@@ -148,6 +146,10 @@ pub enum ParseInputError {
     EmptyInput,
     #[error("Unexpected character: '{0}'")]
     InvalidChar(char),
+    #[error("{0}")]
+    InvalidNumber(#[from] ParseIntError2),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 impl FromStr for Input {
@@ -155,10 +157,15 @@ impl FromStr for Input {
 
     fn from_str(text: &str) -> Result<Self, Self::Err> {
         let mut instructions = Vec::with_capacity(700);
-        for line in text.as_bytes().lines() {
-            let instr = line.try_into()?;
+        for (offset, line) in lines_with_offsets(text.as_bytes()) {
+            let instr = line
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
             instructions.push(instr);
         }
+        if instructions.is_empty() {
+            return Err(ParseInputError::EmptyInput);
+        }
         Ok(Self { instructions })
     }
 }
@@ -1,4 +1,3 @@
-use bstr::ByteSlice;
 use smallvec::SmallVec;
 use std::collections::HashMap;
 use std::fmt::{Debug, Error as FmtError, Formatter};
@@ -6,42 +5,47 @@ use std::ops::{Index, IndexMut};
 use std::str::FromStr;
 use thiserror::Error;
 
-use crate::aoclib::{parse_int, ParseIntError2};
+use crate::aoclib::{lines_with_offsets, scan, CommonParseError};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 19");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1: {} (expected 19 114)", part_1(&example));
-    println!(
-        "|'-Part 2: {} (expected 167 409 079 868 000)",
-        part_2(&example)
-    );
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 382 440)", part_1(&input));
-    println!(
-        "|'-Part 2: {} (expected 136 394 217 540 123)",
-        part_2(&input)
-    );
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 19;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = u64;
+    type Answer2 = u64;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<u64> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<u64> {
+        Some(part_2(input))
+    }
 }
 
 #[must_use]
@@ -265,52 +269,7 @@ impl<'a> TryFrom<&'a [u8]> for Part<Value> {
 
     fn try_from(line: &'a [u8]) -> Result<Self, Self::Error> {
         // {x=1858,m=638,a=1227,s=370}
-        #[cfg(debug_assertions)]
-        if !matches!(&line[..3], b"{x=") {
-            return Err(ParseInputError::ExpectedChar('{'));
-        }
-        let comma = line[3..]
-            .find_byte(b',')
-            .ok_or(ParseInputError::ExpectedChar(','))?;
-        let (x_str, line) = line[3..].split_at(comma);
-        let x: Value = parse_int(x_str)?;
-
-        #[cfg(debug_assertions)]
-        if !matches!(&line[..3], b",m=") {
-            return Err(ParseInputError::ExpectedChar(','));
-        }
-        let comma = line[3..]
-            .find_byte(b',')
-            .ok_or(ParseInputError::ExpectedChar(','))?;
-        let (m_str, line) = line[3..].split_at(comma);
-        let m: Value = parse_int(m_str)?;
-
-        #[cfg(debug_assertions)]
-        if !matches!(&line[..3], b",a=") {
-            return Err(ParseInputError::ExpectedChar(','));
-        }
-        let comma = line[3..]
-            .find_byte(b',')
-            .ok_or(ParseInputError::ExpectedChar(','))?;
-        let (a_str, line) = line[3..].split_at(comma);
-        let a: Value = parse_int(a_str)?;
-
-        #[cfg(debug_assertions)]
-        if !matches!(&line[..3], b",s=") {
-            return Err(ParseInputError::ExpectedChar(','));
-        }
-        let close = line[3..]
-            .find_byte(b'}')
-            .ok_or(ParseInputError::ExpectedChar('}'))?;
-        let (s_str, _line) = line[3..].split_at(close);
-        let s: Value = parse_int(s_str)?;
-
-        #[cfg(debug_assertions)]
-        #[allow(clippy::used_underscore_binding)]
-        if !matches!(_line, b"}") {
-            return Err(ParseInputError::ExpectedChar('}'));
-        }
-
+        let (x, m, a, s) = scan!(line => "{x=" int ",m=" int ",a=" int ",s=" int "}")?;
         Ok(Self::new(x, m, a, s))
     }
 }
@@ -432,12 +391,10 @@ impl<'a> TryFrom<&'a [u8]> for RuleBuilder<'a> {
     fn try_from(rule_str: &'a [u8]) -> Result<Self, Self::Error> {
         // a>1858:kd
         // s<173:A
-        let field: Field = rule_str[0].try_into()?;
-        let condition: Condition = rule_str[1].try_into()?;
-        let (value_str, action_str) = rule_str[2..]
-            .split_once(|&ch| ch == b':')
-            .ok_or(ParseInputError::ExpectedChar(':'))?;
-        let value: Value = parse_int(value_str)?;
+        let (field, condition, value, action_str) = scan!(rule_str => byte byte int ":" ident)?;
+        let field = Field::try_from(field).map_err(|err| CommonParseError::invalid_cell(err, 0))?;
+        let condition =
+            Condition::try_from(condition).map_err(|err| CommonParseError::invalid_cell(err, 1))?;
         Ok(Self {
             field,
             condition,
@@ -490,20 +447,20 @@ impl<'a> TryFrom<&'a [u8]> for WorkflowBuilder<'a> {
 
     fn try_from(line: &'a [u8]) -> Result<Self, Self::Error> {
         // tj{x<2412:qh,s<173:A,x>2448:R,R}
-        let (name, mut rest) = line
-            .split_once(|&ch| ch == b'{')
-            .ok_or(ParseInputError::ExpectedChar('{'))?;
+        let (name, rules_str) = scan!(line => ident "{" until("}") "}")?;
 
+        // The rules are separated by commas, and the fallback comes last
+        let mut rule_strs = rules_str.split(|&ch| ch == b',');
+        let fallback_str = rule_strs.next_back().unwrap_or_default();
+        let mut offset = name.len() + 1;
         let mut rules = SmallVec::new();
-        while let Some((rule_str, tail)) = rest.split_once(|&ch| ch == b',') {
-            rules.push(rule_str.try_into()?);
-            rest = tail;
+        for rule_str in rule_strs {
+            let rule = RuleBuilder::try_from(rule_str)
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+            rules.push(rule);
+            offset += rule_str.len() + 1;
         }
-
-        let fallback_str = rest
-            .split_once(|&ch| ch == b'}')
-            .ok_or(ParseInputError::ExpectedChar('}'))?
-            .0;
+        let (fallback_str,) = scan!(fallback_str => ident).map_err(|err| err.offset_by(offset))?;
 
         Ok(Self::new(name, rules, fallback_str))
     }
@@ -513,12 +470,10 @@ impl<'a> TryFrom<&'a [u8]> for WorkflowBuilder<'a> {
 pub enum ParseInputError {
     #[error("Unexpected character: '{0}'")]
     InvalidChar(char),
-    #[error("Did not find expected char: '{0}'")]
-    ExpectedChar(char),
-    #[error("Not a number: {0:?}")]
-    NotANumber(#[from] ParseIntError2),
     #[error("Invalid rule name")]
     InvalidRuleName,
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 #[derive(Debug, Clone)]
@@ -534,12 +489,14 @@ impl FromStr for Input {
     fn from_str(text: &str) -> Result<Self, Self::Err> {
         let mut name_lookup = HashMap::with_capacity(7 << 7);
         let mut workflow_builders = Vec::with_capacity(539);
-        let mut lines = text.as_bytes().lines();
-        for line in &mut lines {
+        let mut lines = lines_with_offsets(text.as_bytes());
+        for (offset, line) in &mut lines {
             if line.is_empty() {
                 break;
             }
-            let workflow: WorkflowBuilder = line.try_into()?;
+            let workflow: WorkflowBuilder = line
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
             let index = workflow_builders.len();
             name_lookup.insert(workflow.name, index);
             workflow_builders.push(workflow);
@@ -554,8 +511,11 @@ impl FromStr for Input {
                 .ok_or(ParseInputError::InvalidRuleName)?,
         );
         let mut parts = Vec::with_capacity(200);
-        for line in lines {
-            parts.push(line.try_into()?);
+        for (offset, line) in lines {
+            let part = line
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+            parts.push(part);
         }
         Ok(Self {
             workflows,
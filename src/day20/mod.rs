@@ -4,40 +4,51 @@ use std::collections::{hash_map::Entry, HashMap, VecDeque};
 use std::fmt::Debug;
 use thiserror::Error;
 
-use crate::aoclib::lcm;
+use crate::aoclib::{checked_lcm, crt_from, lines_with_offsets, CommonParseError};
+use crate::solution::Solution;
 
 const EXAMPLE1: &str = include_str!("example1.txt");
 const EXAMPLE2: &str = include_str!("example2.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 20");
-
-    println!("++Example 1");
-    let example = Circuit::try_from(EXAMPLE1).expect("Parse example 1");
-    println!("|+-Part 1: {} (expected 32000000)", part_1(&example));
-
-    println!("++Example 2");
-    let example = Circuit::try_from(EXAMPLE2).expect("Parse example 2");
-    println!("|+-Part 1: {} (expected 11687500)", part_1(&example));
-
-    println!("++Input");
-    let input = Circuit::try_from(INPUT).expect("Parse input");
-    println!("|+-Part 1: {} (expected 899848294)", part_1(&input));
-    println!("|'-Part 2: {} (expected 247454898168563)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Circuit<'static> {
     Circuit::try_from(INPUT).expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 20;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] = &[
+        ("example1", EXAMPLE1, ()),
+        ("example2", EXAMPLE2, ()),
+        ("input", INPUT, ()),
+    ];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Circuit<'a>;
+    type Transformed<'a> = Circuit<'a>;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        Circuit::try_from(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        part_2(input)
+    }
 }
 
 #[must_use]
@@ -49,8 +60,11 @@ pub fn part_1(input: &Circuit) -> usize {
     simulator.low_count * simulator.high_count
 }
 
+/// Returns `None` if the circuit has no `rx` module, or if it is not triggered within
+/// [`MAX_PRESSES`] button presses.
 #[must_use]
-pub fn part_2(input: &Circuit) -> usize {
+pub fn part_2(input: &Circuit) -> Option<usize> {
+    let rx_source_index = input.rx_source_index?;
     // The input describes four counters with individual cutoff values. When all FlipFlops
     // connected to a Conjunction become low, the Conjunction will trigger and reset that counter
     // to zero. Each flip-flop contributes a value equal to 2 to the power of their distance from
@@ -60,14 +74,19 @@ pub fn part_2(input: &Circuit) -> usize {
     // values, and their contribution.
     let mut pending = SmallVec::<[_; 8]>::new();
     let mut values = SmallVec::<[_; 4]>::new();
+    let mut visited = 0_u64;
     pending.push((input.broadcast_index, 0, 0));
     while let Some((gate_ix, contribution, counter_ix)) = pending.pop() {
+        if visited & (1 << gate_ix) != 0 {
+            break; // Not a chain of flip-flops
+        }
+        visited |= 1 << gate_ix;
         match input.gates[gate_ix].subtype {
             GateType::FlipFlop => {
                 if input.gates[gate_ix]
-                        .destinations
-                        .iter()
-                        .any(|&ix2| matches!(input.gates[ix2].subtype, GateType::Conjunction))
+                    .destinations
+                    .iter()
+                    .any(|&ix2| matches!(input.gates[ix2].subtype, GateType::Conjunction))
                 {
                     // FlipFlop is connected to a Conjections, so it contributes to one of the final values.
                     values[counter_ix] += contribution;
@@ -93,25 +112,39 @@ pub fn part_2(input: &Circuit) -> usize {
 
     if values.iter().copied().min() > Some(0) {
         // All counters matched the pattern. We don't need to simulate the circuit.
-        return values.iter().copied().reduce(lcm).unwrap_or(0);
+        return lcm_of(values);
     }
 
-    // Fallback if that didn't work: Simulate the circuit until all terminators gets triggered once.
+    // Fallback if that didn't work: Simulate the circuit until each input of the terminator has
+    // been triggered twice. The first trigger is the offset of its cycle, and the time to the
+    // second one the period.
     let mut simulator = CircuitSimulator::new(input);
-    loop {
+    simulator.triggers = smallvec![SmallVec::new(); input.gates[rx_source_index].sources.len()];
+    for _ in 0..MAX_PRESSES {
         simulator.press_button_once();
-        if simulator.cycle_counts.iter().all(Option::is_some) {
-            return simulator
-                .cycle_counts
-                .iter()
-                .flatten()
-                .copied()
-                .reduce(lcm)
-                .unwrap_or(0);
+        if simulator.triggers.iter().all(|presses| presses.len() == 2) {
+            let cycles = simulator.triggers.iter().map(|presses| {
+                let (first, second) = (presses[0] as i128, presses[1] as i128);
+                (first, second - first)
+            });
+            let last_first = simulator.triggers.iter().map(|presses| presses[0]).max()?;
+            let (presses, _) = crt_from(cycles, last_first as i128)?;
+            return usize::try_from(presses).ok();
         }
     }
+    None
+}
+
+/// The lcm of all `values`, or `None` if there are none or it overflows
+fn lcm_of(values: impl IntoIterator<Item = usize>) -> Option<usize> {
+    let mut values = values.into_iter();
+    let first = values.next()?;
+    values.try_fold(first, checked_lcm)
 }
 
+/// Number of button presses to simulate in part 2, before giving up
+pub const MAX_PRESSES: usize = 1 << 20;
+
 #[derive(Debug)]
 struct CircuitSimulator<'a> {
     circuit: &'a Circuit<'a>,
@@ -119,7 +152,8 @@ struct CircuitSimulator<'a> {
     pending: VecDeque<(usize, usize, bool)>,
     low_count: usize,
     high_count: usize,
-    cycle_counts: SmallVec<[Option<usize>; 4]>,
+    /// Button presses at which each input of the terminator was first triggered, up to two
+    triggers: SmallVec<[SmallVec<[usize; 2]>; 4]>,
     button_presses: usize,
 }
 
@@ -131,7 +165,7 @@ impl<'a> CircuitSimulator<'a> {
             pending: VecDeque::with_capacity(50),
             low_count: 0,
             high_count: 0,
-            cycle_counts: smallvec![None; 4],
+            triggers: SmallVec::new(),
             button_presses: 0,
         }
     }
@@ -190,7 +224,11 @@ impl<'a> CircuitSimulator<'a> {
                 .iter()
                 .position(|&i| i == source)
                 .unwrap();
-            self.cycle_counts[source_index] = Some(self.button_presses);
+            if let Some(presses) = self.triggers.get_mut(source_index) {
+                if presses.len() < 2 && presses.last() != Some(&self.button_presses) {
+                    presses.push(self.button_presses);
+                }
+            }
         }
 
         true
@@ -265,7 +303,7 @@ impl<'a> Circuit<'a> {
 }
 
 impl<'a> TryFrom<&'a str> for Circuit<'a> {
-    type Error = ParseInputError<'a>;
+    type Error = ParseInputError;
 
     fn try_from(text: &'a str) -> Result<Self, Self::Error> {
         let mut gate_builders: Vec<GateBuilder<'a>> = Vec::with_capacity(64);
@@ -279,12 +317,20 @@ impl<'a> TryFrom<&'a str> for Circuit<'a> {
         name_lookup.insert(button.name, 0);
         gate_builders.push(button);
 
-        for line in text.lines() {
+        if text.trim().is_empty() {
+            return Err(ParseInputError::EmptyInput);
+        }
+        for (offset, line) in lines_with_offsets(text.as_bytes()) {
             let index = gate_builders.len();
-            let builder: GateBuilder<'a> = line.try_into()?;
+            let builder: GateBuilder<'a> = line
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
             name_lookup.insert(builder.name, index);
             gate_builders.push(builder);
         }
+        if !name_lookup.contains_key(b"broadcaster" as &[u8]) {
+            return Err(ParseInputError::MissingBroadcaster);
+        }
 
         // Make sure all destinations exist
         for index in 0..gate_builders.len() {
@@ -302,6 +348,10 @@ impl<'a> TryFrom<&'a str> for Circuit<'a> {
             }
         }
 
+        // The state of the gates is kept in the bits of a `u64`
+        if gate_builders.len() > 64 {
+            return Err(ParseInputError::TooManyGates(gate_builders.len()));
+        }
         let mut gates = Vec::with_capacity(64);
         for builder in gate_builders {
             gates.push(builder.build(&name_lookup)?);
@@ -329,13 +379,19 @@ impl<'a> TryFrom<&'a str> for Circuit<'a> {
 }
 
 #[derive(Debug, Error)]
-pub enum ParseInputError<'a> {
+pub enum ParseInputError {
     #[error("Input is empty")]
     EmptyInput,
     #[error("Expected arrow")]
     ExpectedArrow,
-    #[error("Invalid name")]
-    InvalidName(&'a [u8]),
+    #[error("No broadcaster module")]
+    MissingBroadcaster,
+    #[error("Too many modules: {0}, at most 64 are supported")]
+    TooManyGates(usize),
+    #[error("Unknown module: {0:?}")]
+    InvalidName(String),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
 
 struct GateBuilder<'a> {
@@ -356,22 +412,26 @@ impl<'a> GateBuilder<'a> {
     pub fn build(
         self,
         name_lookup: &HashMap<&'a [u8], usize>,
-    ) -> Result<Gate<'a>, ParseInputError<'a>> {
+    ) -> Result<Gate<'a>, ParseInputError> {
         let destinations = self
             .destinations
             .iter()
-            .map(|n| Ok(*name_lookup.get(n).ok_or(ParseInputError::InvalidName(n))?))
+            .map(|n| {
+                name_lookup
+                    .get(n)
+                    .copied()
+                    .ok_or_else(|| ParseInputError::InvalidName(n.to_str_lossy().into()))
+            })
             .try_collect()?;
 
         Ok(Gate::new(self.name, self.gate_type, destinations))
     }
 }
 
-impl<'a> TryFrom<&'a str> for GateBuilder<'a> {
-    type Error = ParseInputError<'a>;
+impl<'a> TryFrom<&'a [u8]> for GateBuilder<'a> {
+    type Error = ParseInputError;
 
-    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        let value = value.as_bytes();
+    fn try_from(value: &'a [u8]) -> Result<Self, Self::Error> {
         if value.is_empty() {
             return Err(ParseInputError::EmptyInput);
         }
@@ -382,13 +442,9 @@ impl<'a> TryFrom<&'a str> for GateBuilder<'a> {
             rest => (GateType::Identity, rest),
         };
 
-        let spc = rest.find_byte(b' ').ok_or(ParseInputError::ExpectedArrow)?;
-        let (name, rest) = rest.split_at(spc);
-        #[cfg(debug_assertions)]
-        if !matches!(&rest[..4], b" -> ") {
-            return Err(ParseInputError::ExpectedArrow);
-        }
-        let rest = &rest[4..];
+        let (name, rest) = rest
+            .split_once_str(b" -> ")
+            .ok_or(ParseInputError::ExpectedArrow)?;
 
         let mut destinations = SmallVec::new();
         for piece in rest.split(|&ch| ch == b',') {
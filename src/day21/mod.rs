@@ -3,65 +3,91 @@ use std::fmt::Debug;
 use std::str::FromStr;
 use thiserror::Error;
 
-use crate::aoclib::{CommonParseError, Dir, Grid, Pos, RepeatingGrid};
+use crate::aoclib::{CellByte, CommonParseError, Dir, Grid, Pos, RepeatingGrid};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 21");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1 (6): {} (expected 16)", part_1(&example, 6));
-    println!(
-        "|'-Part 2 (500): {} (expected 167 004)",
-        part_2(&example, 500)
-    );
-    println!(
-        "|'-Part 2 (1 000): {} (expected 668 697)",
-        part_2(&example, 1000)
-    );
-    println!(
-        "|'-Part 2 (5 000): {} (expected 16 733 044)",
-        part_2(&example, 5000)
-    );
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1 (64): {} (expected 3 639)", part_1(&input, 64));
-    println!(
-        "|'-Part 2 (26 501 365): {} (expected 604 592 315 958 630)",
-        part_2(&input, 26_501_365)
-    );
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Garden {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input, 64));
-    black_box(part_2(&input, 26_501_365));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 21;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] = &[
+        ("example", EXAMPLE, Steps::new(6, 500)),
+        ("example_1000", EXAMPLE, Steps::new(6, 1000)),
+        ("example_5000", EXAMPLE, Steps::new(6, 5000)),
+        ("input", INPUT, Steps::new(64, 26_501_365)),
+    ];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Garden;
+    type Transformed<'a> = Garden;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = i64;
+    type Answer2 = i64;
+    type Params = Steps;
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, steps: &Steps) -> Option<i64> {
+        Some(part_1(input, steps.part_1))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, steps: &Steps) -> Option<i64> {
+        Some(part_2(input, steps.part_2))
+    }
+}
+
+/// Number of steps the elf takes in each part
+#[derive(Debug, Clone, Copy)]
+pub struct Steps {
+    pub part_1: usize,
+    pub part_2: usize,
+}
+
+impl Steps {
+    const fn new(part_1: usize, part_2: usize) -> Self {
+        Self { part_1, part_2 }
+    }
 }
 
+/// The elf can end on the plots within `target_dist` steps that are an even number of steps away
+/// from where `target_dist` takes it, by stepping back and forth.
 #[must_use]
 pub fn part_1(garden: &Garden, target_dist: usize) -> i64 {
-    plots_after_steps(garden, target_dist, false)
+    let distances = garden.grid.bfs([garden.start_pos], |_, to| {
+        !matches!(garden.grid[to], Tile::Rock)
+    });
+    let plots = distances
+        .into_distances()
+        .iter()
+        .filter(|(_, &dist)| {
+            dist.is_some_and(|dist| dist <= target_dist && (target_dist - dist).is_multiple_of(2))
+        })
+        .count();
+    i64::try_from(plots).unwrap()
 }
 
 #[must_use]
 pub fn part_2(garden: &Garden, target_dist: usize) -> i64 {
-    plots_after_steps(garden, target_dist, true)
+    plots_after_steps(garden, target_dist)
 }
 
 #[allow(clippy::cast_possible_wrap)]
-fn plots_after_steps(garden: &Garden, target_dist: usize, large: bool) -> i64 {
-    let mut walker = Walker::new(&garden.grid, garden.start_pos, large);
+fn plots_after_steps(garden: &Garden, target_dist: usize) -> i64 {
+    let mut walker = Walker::new(&garden.grid, garden.start_pos);
     let size = garden.grid.width();
     let mut samples = Vec::with_capacity(6);
     for step in 0.. {
@@ -109,16 +135,11 @@ struct Walker<'a> {
 }
 
 impl<'a> Walker<'a> {
-    pub fn new(grid: &'a Grid<Tile>, start_pos: Pos, large: bool) -> Self {
-        let (hs_capacity, vec_capacity) = if large {
-            (7 << 15, 2_000)
-        } else {
-            (7 << 10, 300)
-        };
-        let mut current = HashSet::with_capacity(hs_capacity);
-        let mut current_fringe = Vec::with_capacity(vec_capacity);
-        let next = HashSet::with_capacity(hs_capacity);
-        let next_fringe = Vec::with_capacity(vec_capacity);
+    pub fn new(grid: &'a Grid<Tile>, start_pos: Pos) -> Self {
+        let mut current = HashSet::with_capacity(7 << 15);
+        let mut current_fringe = Vec::with_capacity(2_000);
+        let next = HashSet::with_capacity(7 << 15);
+        let next_fringe = Vec::with_capacity(2_000);
         current_fringe.push(start_pos);
         current.insert(start_pos);
         Self {
@@ -133,7 +154,7 @@ impl<'a> Walker<'a> {
     pub fn take_step(&mut self) {
         self.next_fringe.clear();
         for &pos in &self.current_fringe {
-            for dir in [Dir::N, Dir::E, Dir::S, Dir::W] {
+            for dir in Dir::ALL {
                 let next_pos = pos + dir;
                 if matches!(self.grid[next_pos], Tile::GardenPlot) && self.next.insert(next_pos) {
                     self.next_fringe.push(next_pos);
@@ -159,10 +180,16 @@ enum Tile {
 
 impl Debug for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(self.to_byte()))
+    }
+}
+
+impl CellByte for Tile {
+    fn to_byte(self) -> u8 {
         match self {
-            Self::GardenPlot => write!(f, "."),
-            Self::Rock => write!(f, "#"),
-            Self::Start => write!(f, "S"),
+            Self::GardenPlot => b'.',
+            Self::Rock => b'#',
+            Self::Start => b'S',
         }
     }
 }
@@ -202,7 +229,9 @@ impl Garden {
 pub enum ParseInputError {
     #[error("Unexpected character: '{0}'")]
     InvalidChar(char),
-    #[error("{0:?}")]
+    #[error("Missing start")]
+    MissingStart,
+    #[error("{0}")]
     CommonError(#[from] CommonParseError),
 }
 
@@ -211,7 +240,9 @@ impl FromStr for Garden {
 
     fn from_str(text: &str) -> Result<Self, Self::Err> {
         let mut grid: Grid<Tile> = text.parse()?;
-        let start_pos = grid.position(|p| matches!(p, Tile::Start)).unwrap();
+        let start_pos = grid
+            .position(|p| matches!(p, Tile::Start))
+            .ok_or(ParseInputError::MissingStart)?;
         grid.set(start_pos, Tile::GardenPlot);
         let mut home_row_free = grid
             .get_row(start_pos.row())
@@ -5,38 +5,49 @@ use std::num::ParseIntError;
 use std::str::FromStr;
 use thiserror::Error;
 
-use crate::aoclib::{CommonParseError, Grid, Pos};
+use crate::aoclib::{lines_with_offsets, CommonParseError, Grid, Pos};
+use crate::solution::Solution;
 
 type Coordinate = crate::aoclib::Coordinate<u16>;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 22");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1: {} (expected 5)", part_1(&example));
-    println!("|'-Part 2: {} (expected 7)", part_2(&example));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!("|+-Part 1: {} (expected 465)", part_1(&input));
-    println!("|'-Part 2: {} (expected 79 042)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Board {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 22;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Board;
+    type Transformed<'a> = Board;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_1(input))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        Some(part_2(input))
+    }
 }
 
 #[allow(clippy::cast_possible_wrap)]
@@ -225,10 +236,15 @@ impl<'a> TryFrom<&'a [u8]> for Piece {
             .find_byte(b'~')
             .ok_or(ParseInputError::ExpectedChar('~'))?;
         let start: Coordinate = s[..tilde].try_into()?;
-        let end: Coordinate = s[tilde + 1..].try_into()?;
-        debug_assert!(start.x <= end.x);
-        debug_assert!(start.y <= end.y);
-        debug_assert!(start.z <= end.z);
+        let end: Coordinate = s[tilde + 1..]
+            .try_into()
+            .map_err(|err: CommonParseError| err.offset_by(tilde + 1))?;
+        if start.x > end.x || start.y > end.y || start.z > end.z {
+            return Err(ParseInputError::ReversedPiece);
+        }
+        if start.z == 0 {
+            return Err(ParseInputError::BelowGround);
+        }
         Ok(Self::new(start, end))
     }
 }
@@ -253,12 +269,17 @@ impl FromStr for Board {
         let mut min = Coordinate::new(u16::MAX, u16::MAX, u16::MAX);
         let mut max = Coordinate::new(u16::MIN, u16::MIN, u16::MIN);
         let mut pieces = Vec::with_capacity(1400);
-        for line in text.as_bytes().lines() {
-            let piece: Piece = line.try_into()?;
+        for (offset, line) in lines_with_offsets(text.as_bytes()) {
+            let piece: Piece = line
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
             min = min.min_fields(piece.low);
             max = max.max_fields(piece.high);
             pieces.push(piece);
         }
+        if pieces.is_empty() {
+            return Err(ParseInputError::EmptyInput);
+        }
         pieces.sort_unstable_by_key(|p| (p.high.z, p.low.x, p.low.y));
         Ok(Self::new(pieces, min, max))
     }
@@ -266,10 +287,16 @@ impl FromStr for Board {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
+    #[error("Brick ends before it starts")]
+    ReversedPiece,
+    #[error("Brick is not above the ground")]
+    BelowGround,
     #[error("Expected character: '{0}'")]
     ExpectedChar(char),
     #[error("Not an integer: {0:?}")]
     Integer(#[from] ParseIntError),
-    #[error("{0:?}")]
+    #[error("{0}")]
     CommonError(#[from] CommonParseError),
 }
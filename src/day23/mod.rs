@@ -5,26 +5,12 @@ use std::ops::{BitAnd, Deref};
 use std::str::FromStr;
 use thiserror::Error;
 
-use crate::aoclib::{CommonParseError, Dir, Grid, Pos};
+use crate::aoclib::{CellByte, CommonParseError, Dir, Grid, Pos};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 23");
-
-    println!("++Example");
-    let example = Graph::from(&EXAMPLE.parse::<Map>().expect("Parse example"));
-    println!("|+-Part 1: {} (expected 94)", part_1(&example));
-    println!("|'-Part 2: {} (expected 154)", part_2(&example));
-
-    println!("++Input");
-    let input = Graph::from(&INPUT.parse::<Map>().expect("Parse input"));
-    println!("|+-Part 1: {} (expected 2402)", part_1(&input));
-    println!("|'-Part 2: {} (expected 6450)", part_2(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Map {
     INPUT.parse::<Map>().expect("Parse input")
@@ -35,27 +21,56 @@ pub fn transform_test_input(input: &Map) -> Graph {
     Graph::from(input)
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = transform_test_input(&parse_test_input());
-    black_box(part_1(&input));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 23;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+    const TRANSFORMS: bool = true;
+
+    type Input<'a> = Map;
+    type Transformed<'a> = Graph;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        Graph::from(&input)
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        part_1(input)
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        part_2(input)
+    }
 }
 
+/// Returns `None` if the goal can not be reached.
 #[must_use]
-pub fn part_1(graph: &Graph) -> usize {
+pub fn part_1(graph: &Graph) -> Option<usize> {
     graph.longest_path::<true>()
 }
 
+/// Returns `None` if the goal can not be reached.
 #[must_use]
-pub fn part_2(graph: &Graph) -> usize {
+pub fn part_2(graph: &Graph) -> Option<usize> {
     graph.longest_path::<false>()
 }
 
 pub struct Graph {
     nodes: Vec<Node>,
     start_ix: usize,
-    goal_ix: usize,
+    /// `None` if no path leads to the goal
+    goal_ix: Option<usize>,
 }
 
 impl Graph {
@@ -63,15 +78,21 @@ impl Graph {
         self.nodes.len()
     }
 
-    fn longest_path<const SLOPES_ONE_WAY: bool>(&self) -> usize {
-        let mut max_dist = 0;
+    /// Returns `None` if the goal can not be reached, or if there are too many intersections to
+    /// keep the visited ones in a bit set.
+    fn longest_path<const SLOPES_ONE_WAY: bool>(&self) -> Option<usize> {
+        let goal_ix = self.goal_ix?;
+        if self.len() > u64::BITS as usize {
+            return None;
+        }
+        let mut max_dist = None;
 
         let mut pending = Vec::with_capacity(self.len());
         pending.push((self.start_ix, 0, 0_u64));
 
         while let Some((node_ix, dist, visited)) = pending.pop() {
-            if node_ix == self.goal_ix {
-                max_dist = max_dist.max(dist);
+            if node_ix == goal_ix {
+                max_dist = max_dist.max(Some(dist));
                 continue;
             }
 
@@ -152,8 +173,8 @@ impl GraphBuiler {
     pub fn build(self) -> Graph {
         Graph {
             nodes: self.nodes,
-            start_ix: self.start_ix.unwrap(),
-            goal_ix: self.goal_ix.unwrap(),
+            start_ix: self.start_ix.expect("The start node is added first"),
+            goal_ix: self.goal_ix,
         }
     }
 
@@ -307,13 +328,16 @@ enum Tile {
 
 impl Debug for Tile {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", char::from(self.to_byte()))
+    }
+}
+
+impl CellByte for Tile {
+    fn to_byte(self) -> u8 {
         match self {
-            Self::Open => write!(f, "."),
-            Self::Blocked => write!(f, "#"),
-            Self::Slope(Dir::N) => write!(f, "^"),
-            Self::Slope(Dir::E) => write!(f, ">"),
-            Self::Slope(Dir::S) => write!(f, "v"),
-            Self::Slope(Dir::W) => write!(f, "<"),
+            Self::Open => b'.',
+            Self::Blocked => b'#',
+            Self::Slope(dir) => dir.to_arrow(),
         }
     }
 }
@@ -325,11 +349,7 @@ impl TryFrom<u8> for Tile {
         Ok(match value {
             b'.' => Tile::Open,
             b'#' => Tile::Blocked,
-            b'^' => Tile::Slope(Dir::N),
-            b'>' => Tile::Slope(Dir::E),
-            b'v' => Tile::Slope(Dir::S),
-            b'<' => Tile::Slope(Dir::W),
-            ch => return Err(ParseInputError::InvalidChar(ch as char)),
+            ch => Tile::Slope(Dir::from_arrow(ch).ok_or(ParseInputError::InvalidChar(ch as char))?),
         })
     }
 }
@@ -348,7 +368,7 @@ impl Map {
             None | Some(Tile::Blocked) => smallvec![],
             Some(tile) => {
                 let mut neighbors: SmallVec<[_; 4]> = SmallVec::new();
-                for next_dir in [Dir::N, Dir::E, Dir::S, Dir::W] {
+                for next_dir in Dir::ALL {
                     let next_pos = pos + next_dir;
                     let cur_along = match tile {
                         // Current is slope in current direction
@@ -390,6 +410,16 @@ impl FromStr for Map {
         let grid: Grid<Tile> = text.parse()?;
         let start = Pos::new(0, 1);
         let goal = Pos::new(grid.height() as isize - 1, grid.width() as isize - 2);
+        let is_path = |pos| {
+            grid.get(pos)
+                .is_some_and(|tile| !matches!(tile, Tile::Blocked))
+        };
+        if !is_path(start) {
+            return Err(ParseInputError::MissingStart);
+        }
+        if !is_path(goal) {
+            return Err(ParseInputError::MissingGoal);
+        }
         Ok(Self { grid, start, goal })
     }
 }
@@ -440,6 +470,10 @@ impl BitAnd for EdgeDirection {
 pub enum ParseInputError {
     #[error("Unexpected character: '{0}'")]
     InvalidChar(char),
-    #[error("{0:?}")]
+    #[error("Expected a path in the second column of the first row")]
+    MissingStart,
+    #[error("Expected a path in the second to last column of the last row")]
+    MissingGoal,
+    #[error("{0}")]
     CommonError(#[from] CommonParseError),
 }
@@ -1,44 +1,52 @@
 use std::num::ParseFloatError;
-use std::ops::RangeBounds;
+use std::ops::{RangeBounds, RangeInclusive};
 use std::str::FromStr;
 use thiserror::Error;
 
-use crate::aoclib::{CommonParseError, Coordinate};
+use crate::aoclib::{lines_with_offsets, CommonParseError, Coordinate};
+use crate::solution::Solution;
 
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 24");
-
-    println!("++Example");
-    let example = EXAMPLE.parse().expect("Parse example");
-    println!("|+-Part 1: {} (expected 2)", part_1(&example, 7.0..=27.0));
-    println!("|'-Part 2: {} (expected 47)", part_2(&example));
-
-    println!("++Input");
-    let input = INPUT.parse().expect("Parse input");
-    println!(
-        "|+-Part 1: {} (expected 31_921)",
-        part_1(&input, 2e14..=4e14)
-    );
-    println!(
-        "|'-Part 2: {} (expected 761_691_907_059_631)",
-        part_2(&input)
-    );
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> Input {
     INPUT.parse().expect("Parse input")
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input, 2e14..=4e14));
-    black_box(part_2(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 24;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] = &[
+        ("example", EXAMPLE, 7.0..=27.0),
+        ("input", INPUT, 2e14..=4e14),
+    ];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+
+    type Input<'a> = Input;
+    type Transformed<'a> = Input;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = i128;
+    /// Test area of part 1, for both coordinates
+    type Params = RangeInclusive<f64>;
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        text.parse()
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, test_area: &RangeInclusive<f64>) -> Option<usize> {
+        Some(part_1(input, test_area.clone()))
+    }
+
+    fn part_2(input: &Self::Transformed<'_>, _test_area: &RangeInclusive<f64>) -> Option<i128> {
+        part_2(input)
+    }
 }
 
 #[must_use]
@@ -64,17 +72,18 @@ struct Intersection2d {
 
 #[allow(clippy::cast_precision_loss)]
 fn intersection_2d(hail1: &Hail, hail2: &Hail) -> Option<Intersection2d> {
-    let denomenator = hail1.velocity.x * hail2.velocity.y - hail2.velocity.x * hail1.velocity.y;
+    let (v1, v2) = (hail1.velocity.xy(), hail2.velocity.xy());
+    let denomenator = v1.cross(v2);
     if denomenator == 0 {
         return None;
     }
-    let numerator1 = (hail1.position.y - hail2.position.y) * hail2.velocity.x
-        + (hail2.position.x - hail1.position.x) * hail2.velocity.y;
+    let delta = hail2.position.xy() - hail1.position.xy();
+    // Times of reaching the intersection, times the denominator
+    let numerator1 = delta.cross(v2);
     if numerator1.is_negative() ^ denomenator.is_negative() {
         return None;
     }
-    let numerator2 = (hail1.position.y - hail2.position.y) * hail1.velocity.x
-        + (hail2.position.x - hail1.position.x) * hail1.velocity.y;
+    let numerator2 = delta.cross(v1);
     if numerator2.is_negative() ^ denomenator.is_negative() {
         return None;
     }
@@ -86,35 +95,48 @@ fn intersection_2d(hail1: &Hail, hail2: &Hail) -> Option<Intersection2d> {
 }
 
 #[must_use]
-pub fn part_2(input: &Input) -> i128 {
-    let p1 = &input.hails[0].position;
-    let v1 = &input.hails[0].velocity;
-    let p2 = &input.hails[1].position;
-    let v2 = &input.hails[1].velocity;
-    let p3 = &input.hails[2].position;
-    let v3 = &input.hails[2].velocity;
-    let num1 = (p1.x * (p2.y - p3.y) + p2.x * (p3.y - p1.y) + p3.x * (p1.y - p2.y)) * (v2.z - v3.z)
-        + (p2.x * (p1.z - p3.z) + p1.x * (-p2.z + p3.z) + p3.x * (p2.z - p1.z)) * (v2.y - v3.y)
-        + (p1.y * (p2.z - p3.z) + p2.y * (-p1.z + p3.z) + p3.y * (p1.z - p2.z)) * (v2.x - v3.x);
-    let denom1 = (v1.y * (v2.z - v3.z) + v2.y * (-v1.z + v3.z) + v3.y * (v1.z - v2.z))
-        * (p2.x - p3.x)
-        + ((v2.x - v3.x) * v1.z + (-v1.x + v3.x) * v2.z + (v1.x - v2.x) * v3.z) * (p2.y - p3.y)
-        + (v1.x * (v2.y - v3.y) + v2.x * (-v1.y + v3.y) + v3.x * (v1.y - v2.y)) * (p2.z - p3.z);
-    let num2 = (p1.y * (p2.z - p3.z) + p2.y * (-p1.z + p3.z) + p3.y * (p1.z - p2.z))
-        * (v1.x - v3.x)
-        + ((p2.x - p3.x) * p1.z + (-p1.x + p3.x) * p2.z + (p1.x - p2.x) * p3.z) * (v1.y - v3.y)
-        + (p1.x * (p2.y - p3.y) + p2.x * (p3.y - p1.y) + p3.x * (p1.y - p2.y)) * (v1.z - v3.z);
-    let denom2 = (v1.y * (v2.z - v3.z) + v2.y * (-v1.z + v3.z) + v3.y * (v1.z - v2.z))
-        * (p1.x - p3.x)
-        + ((v2.x - v3.x) * v1.z + (-v1.x + v3.x) * v2.z + (v1.x - v2.x) * v3.z) * (p1.y - p3.y)
-        + (v1.x * (v2.y - v3.y) + v2.x * (-v1.y + v3.y) + v3.x * (v1.y - v2.y)) * (p1.z - p3.z);
-    let time1 = num1 / denom1;
-    let time2 = num2 / denom2;
-    let collission1 = *p1 + *v1 * time1;
-    let collission2 = *p2 + *v2 * time2;
-    let velocity = (collission2 - collission1) / (time2 - time1);
-    let position = collission1 - velocity * time1;
-    position.x + position.y + position.z
+/// Returns `None` if there are fewer than three hailstones, or no rock thrown from whole
+/// coordinates with a whole velocity hits the first three at whole times.
+pub fn part_2(input: &Input) -> Option<i128> {
+    // Seen from the first hailstone, the rock passes through the origin, so it lies in the plane
+    // through the origin and the path of each other hailstone
+    let [hail1, hail2, hail3, ..] = &input.hails[..] else {
+        return None;
+    };
+    let (p2, v2) = (
+        hail2.position - hail1.position,
+        hail2.velocity - hail1.velocity,
+    );
+    let (p3, v3) = (
+        hail3.position - hail1.position,
+        hail3.velocity - hail1.velocity,
+    );
+    let normal2 = p2.cross(v2);
+    let normal3 = p3.cross(v3);
+    // Times when the second and third hailstones cross the plane of the other, and the path of
+    // the rock
+    let time2 = exact_div(-normal3.dot(p2), normal3.dot(v2))?;
+    let time3 = exact_div(-normal2.dot(p3), normal2.dot(v3))?;
+    let collission2 = hail2.position + hail2.velocity * time2;
+    let collission3 = hail3.position + hail3.velocity * time3;
+    let distance = collission3 - collission2;
+    let duration = time3 - time2;
+    let velocity = Coordinate::new(
+        exact_div(distance.x, duration)?,
+        exact_div(distance.y, duration)?,
+        exact_div(distance.z, duration)?,
+    );
+    let position = collission2 - velocity * time2;
+    Some(position.x + position.y + position.z)
+}
+
+/// `a / b`, if `b` divides `a` and the quotient doesn't overflow
+fn exact_div(a: i128, b: i128) -> Option<i128> {
+    if a.checked_rem(b)? == 0 {
+        a.checked_div(b)
+    } else {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -123,16 +145,19 @@ struct Hail {
     velocity: Coordinate<i128>,
 }
 
-impl FromStr for Hail {
-    type Err = ParseInputError;
+impl TryFrom<&[u8]> for Hail {
+    type Error = ParseInputError;
 
-    fn from_str(line: &str) -> Result<Self, Self::Err> {
+    fn try_from(line: &[u8]) -> Result<Self, Self::Error> {
         let (pos_str, vel_str) = line
-            .split_once('@')
+            .split_once(|&ch| ch == b'@')
             .ok_or(ParseInputError::ExpectedChar('@'))?;
+        let vel_offset = pos_str.len() + 1;
         Ok(Self {
-            position: pos_str.as_bytes().try_into()?,
-            velocity: vel_str.as_bytes().try_into()?,
+            position: pos_str.try_into()?,
+            velocity: vel_str
+                .try_into()
+                .map_err(|err: CommonParseError| err.offset_by(vel_offset))?,
         })
     }
 }
@@ -147,8 +172,14 @@ impl FromStr for Input {
 
     fn from_str(text: &str) -> Result<Self, Self::Err> {
         let mut hails = Vec::new();
-        for line in text.lines() {
-            hails.push(line.parse()?);
+        for (offset, line) in lines_with_offsets(text.as_bytes()) {
+            let hail = line
+                .try_into()
+                .map_err(|err| CommonParseError::invalid_part(err, offset))?;
+            hails.push(hail);
+        }
+        if hails.is_empty() {
+            return Err(ParseInputError::EmptyInput);
         }
         Ok(Self { hails })
     }
@@ -156,6 +187,8 @@ impl FromStr for Input {
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
     #[error("Did not find expected char: '{0}'")]
     ExpectedChar(char),
     #[error("{0}")]
@@ -6,22 +6,12 @@ use std::collections::HashMap;
 use std::fmt::Debug;
 use thiserror::Error;
 
+use crate::aoclib::{fields_with_offsets, lines_with_offsets, CommonParseError};
+use crate::solution::Solution;
+
 const EXAMPLE: &str = include_str!("example.txt");
 const INPUT: &str = include_str!("input.txt");
 
-pub fn run() {
-    println!(".Day 25");
-
-    println!("++Example");
-    let example = EXAMPLE.try_into().expect("Parse example");
-    println!("|+-Part 1: {} (expected 54)", part_1(&example));
-
-    println!("++Input");
-    let input = INPUT.try_into().expect("Parse input");
-    println!("|+-Part 1: {} (expected 507626)", part_1(&input));
-    println!("')");
-}
-
 #[must_use]
 pub fn parse_test_input() -> WiringDiagram<'static> {
     INPUT.try_into().expect("Parse input")
@@ -37,19 +27,51 @@ pub fn run_cycles(input: &WiringDiagram, cycles: usize) -> usize {
     sum
 }
 
-pub fn profile() {
-    use std::hint::black_box;
-    let input = parse_test_input();
-    black_box(part_1(&input));
+pub struct Puzzle;
+
+impl Solution for Puzzle {
+    const DAY: u8 = 25;
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)] =
+        &[("example", EXAMPLE, ()), ("input", INPUT, ())];
+    const ANSWERS: &'static str = include_str!("answers.toml");
+    const HAS_PART_2: bool = false;
+
+    type Input<'a> = WiringDiagram<'a>;
+    type Transformed<'a> = WiringDiagram<'a>;
+    type ParseError<'a> = ParseInputError;
+    type Answer1 = usize;
+    type Answer2 = usize;
+    type Params = ();
+
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>> {
+        WiringDiagram::try_from(text)
+    }
+
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_> {
+        input
+    }
+
+    fn part_1(input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        part_1(input)
+    }
+
+    fn part_2(_input: &Self::Transformed<'_>, _params: &()) -> Option<usize> {
+        unreachable!("Day 25 has no second part")
+    }
 }
 
+/// Returns `None` if no cut of three wires is found in [`MAX_TRIALS`] attempts.
 #[must_use]
-pub fn part_1(input: &WiringDiagram) -> usize {
+pub fn part_1(input: &WiringDiagram) -> Option<usize> {
     let mut karger = Karger::new(input);
-    let (a, b) = karger.run_to_completion();
-    a * b
+    let (a, b) = karger.run_to_completion()?;
+    Some(a * b)
 }
 
+/// Number of random contractions to try before giving up. The real input needs a few hundred at
+/// most.
+pub const MAX_TRIALS: usize = 10_000;
+
 /// Implementation of Karger's algoritm. <https://en.wikipedia.org/wiki/Karger%27s_algorithm>
 /// Adapted from <https://github.com/kuviman/advent_of_code_2023/blob/d6838bfca881c38134b357636604bf98b69833dd/src/bin/day25/main.rs#L74>
 struct Karger<'a> {
@@ -91,17 +113,15 @@ impl<'a> Karger<'a> {
             .count()
     }
 
-    fn run_to_completion(&mut self) -> (usize, usize) {
-        loop {
+    fn run_to_completion(&mut self) -> Option<(usize, usize)> {
+        for _ in 0..MAX_TRIALS {
             let edges_left = self.single_cycle();
 
             if edges_left == 3 {
-                return self
-                    .union_find
-                    .get_two_components()
-                    .expect("Two components left");
+                return self.union_find.get_two_components();
             }
         }
+        None
     }
 }
 
@@ -214,10 +234,19 @@ impl<'a> TryFrom<&'a str> for WiringDiagram<'a> {
         let mut name_lookup = HashMap::new();
         let mut components = Vec::new();
         let mut edges = Vec::new();
-        for line in text.lines() {
-            let (name, rest) = line
-                .split_once(':')
-                .ok_or(ParseInputError::ExpectedChar(':'))?;
+        for (offset, line) in lines_with_offsets(text.as_bytes()) {
+            // Lines end at an ASCII byte, so this is a whole number of chars
+            let line = &text[offset..offset + line.len()];
+            let invalid_name = |name: &str, start: usize| {
+                let err = ParseInputError::InvalidName(name.to_string());
+                CommonParseError::invalid_part(err, offset + start)
+            };
+            let (name, rest) = line.split_once(':').ok_or_else(|| {
+                CommonParseError::invalid_part(ParseInputError::ExpectedChar(':'), offset)
+            })?;
+            if name.is_empty() || !name.bytes().all(|ch| ch.is_ascii_alphanumeric()) {
+                return Err(invalid_name(name, 0));
+            }
             let node_ix = match name_lookup.entry(name) {
                 Entry::Occupied(o) => *o.get(),
                 Entry::Vacant(v) => {
@@ -227,7 +256,12 @@ impl<'a> TryFrom<&'a str> for WiringDiagram<'a> {
                     ix
                 }
             };
-            for neighbor in rest.trim_start().split(' ') {
+            for (start, neighbor) in fields_with_offsets(rest.as_bytes()) {
+                let start = name.len() + 1 + start;
+                let neighbor = &line[start..start + neighbor.len()];
+                if !neighbor.bytes().all(|ch| ch.is_ascii_alphanumeric()) {
+                    return Err(invalid_name(neighbor, start));
+                }
                 let neighbor_ix = match name_lookup.entry(neighbor) {
                     Entry::Occupied(o) => *o.get(),
                     Entry::Vacant(v) => {
@@ -240,12 +274,30 @@ impl<'a> TryFrom<&'a str> for WiringDiagram<'a> {
                 edges.push([node_ix, neighbor_ix]);
             }
         }
+        if components.is_empty() {
+            return Err(ParseInputError::EmptyInput);
+        }
+        let mut union_find = UnionFind::new(components.len());
+        for &[a, b] in &edges {
+            union_find.union(a, b);
+        }
+        if union_find.num_components > 1 {
+            return Err(ParseInputError::Disconnected);
+        }
         Ok(Self { components, edges })
     }
 }
 
 #[derive(Debug, Error)]
 pub enum ParseInputError {
+    #[error("Input is empty")]
+    EmptyInput,
+    #[error("Invalid component name: {0:?}")]
+    InvalidName(String),
+    #[error("The components are not all connected")]
+    Disconnected,
     #[error("Expected character: '{0}'")]
     ExpectedChar(char),
+    #[error("{0}")]
+    CommonError(#[from] CommonParseError),
 }
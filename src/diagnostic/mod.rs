@@ -0,0 +1,191 @@
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use crate::aoclib::{CommonParseError, ParseIntError2};
+
+/// Position of a parse error in the puzzle input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Location {
+    /// Byte offset from the start of the input
+    pub offset: usize,
+    /// Line number, starting at 1
+    pub line: usize,
+    /// Byte offset from the start of the line, starting at 1
+    pub column: usize,
+    /// The line containing the error, without the line ending
+    pub source_line: String,
+}
+
+impl Location {
+    /// Locate the byte `offset` in `text`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `offset` is past the end of `text`.
+    #[must_use]
+    pub fn from_offset(text: &str, offset: usize) -> Self {
+        let bytes = text.as_bytes();
+        let line_start = bytes[..offset]
+            .iter()
+            .rposition(|&ch| ch == b'\n')
+            .map_or(0, |ix| ix + 1);
+        let line_end = bytes[offset..]
+            .iter()
+            .position(|&ch| ch == b'\n')
+            .map_or(bytes.len(), |ix| offset + ix);
+        let source_line = String::from_utf8_lossy(&bytes[line_start..line_end]);
+        Self {
+            offset,
+            line: text[..line_start].matches('\n').count() + 1,
+            column: offset - line_start + 1,
+            source_line: source_line.trim_end_matches('\r').to_string(),
+        }
+    }
+
+    /// Find where in `text` the error happened, from the errors of the common parsers among the
+    /// sources of `err`. Each of them is located relative to the one before it, such as an
+    /// integer in a line, so their offsets add up. Returns `None` if none of them know their
+    /// position, or if it is outside of `text`.
+    #[must_use]
+    pub fn of_error(err: &dyn Error, text: &str) -> Option<Self> {
+        let mut offset = None;
+        let mut source = err.source();
+        while let Some(err) = source {
+            let found = if let Some(err) = err.downcast_ref::<ParseIntError2>() {
+                Some(err.offset)
+            } else if let Some(err) = err.downcast_ref::<CommonParseError>() {
+                err.offset()
+            } else {
+                None
+            };
+            if let Some(found) = found {
+                offset = Some(offset.unwrap_or(0) + found);
+            }
+            source = err.source();
+        }
+        offset
+            .filter(|&offset| offset <= text.len())
+            .map(|offset| Self::from_offset(text, offset))
+    }
+
+    /// The line containing the error, with a caret under the offending byte
+    ///
+    /// ```text
+    /// 3 | 1 2 x 4
+    ///   |     ^
+    /// ```
+    #[must_use]
+    pub fn caret(&self) -> String {
+        let number = self.line.to_string();
+        let indent: String = self
+            .source_line
+            .char_indices()
+            .take_while(|&(ix, _)| ix + 1 < self.column)
+            .map(|(_, ch)| if ch == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "{number} | {}\n{:width$} | {indent}^",
+            self.source_line,
+            "",
+            width = number.len()
+        )
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aoclib::{lines_with_offsets, parse_int, Grid};
+    use crate::solution::Parts;
+
+    #[test]
+    fn from_offset() {
+        let text = "ab\r\ncd\nef";
+        let location = Location::from_offset(text, 5);
+        assert_eq!((location.line, location.column), (2, 2));
+        assert_eq!(location.source_line, "cd");
+        assert_eq!(Location::from_offset(text, 0).source_line, "ab");
+        assert_eq!(Location::from_offset(text, text.len()).column, 3);
+    }
+
+    #[test]
+    fn caret() {
+        let location = Location::from_offset("1 2\n1 2 x 4", 8);
+        assert_eq!(location.caret(), "2 | 1 2 x 4\n  |     ^");
+    }
+
+    #[test]
+    fn grid_error() {
+        let text = "..\n.x\n";
+        let err = text.parse::<Grid<Cell>>().unwrap_err();
+        let location = Location::of_error(&Wrapper(err), text).unwrap();
+        assert_eq!((location.line, location.column), (2, 2));
+    }
+
+    #[test]
+    fn nested_offsets_add_up() {
+        let text = "1 2\n3 4 x5\n";
+        let err = lines_with_offsets(text.as_bytes())
+            .map(|(offset, line)| {
+                let (_, last) = line.rsplit_once(|&ch| ch == b' ').unwrap();
+                let start = line.len() - last.len();
+                parse_int::<u32>(last)
+                    .map_err(|err| CommonParseError::from(err.offset_by(start)))
+                    .map_err(|err| CommonParseError::invalid_part(err, offset))
+            })
+            .find_map(Result::err)
+            .unwrap();
+        let location = Location::of_error(&Wrapper(err), text).unwrap();
+        assert_eq!((location.line, location.column), (2, 5));
+    }
+
+    #[test]
+    fn day_error() {
+        let text = "Time: 7 x\nDistance: 9 40\n";
+        let err = crate::get_day(6)
+            .unwrap()
+            .solve("input", text, Parts::BOTH)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Not an integer: Invalid integer: invalid digit"
+        );
+        // The crate level error does not repeat the message of the day error as a source
+        let source = err.source().unwrap();
+        assert_eq!(source.to_string(), "Invalid integer: invalid digit");
+        let location = Location::of_error(&err, text).unwrap();
+        assert_eq!((location.line, location.column), (1, 9));
+    }
+
+    #[test]
+    fn unlocated_error() {
+        let err = Wrapper(CommonParseError::EmptyInput);
+        assert_eq!(Location::of_error(&err, ""), None);
+    }
+
+    /// `of_error` looks at the sources of the error it is given, like the day errors in
+    /// [`crate::error::Error`]
+    #[derive(Debug, thiserror::Error)]
+    #[error("{0}")]
+    struct Wrapper(#[source] CommonParseError);
+
+    #[derive(Debug, Clone, Copy, Default)]
+    struct Cell;
+
+    impl TryFrom<u8> for Cell {
+        type Error = CommonParseError;
+
+        fn try_from(ch: u8) -> Result<Self, Self::Error> {
+            match ch {
+                b'.' => Ok(Self),
+                ch => Err(CommonParseError::ExpectedChar(ch as char)),
+            }
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use std::io;
+
+use thiserror::Error;
+
+/// Errors from running the puzzles: unreadable input, or input a day could not parse.
+///
+/// The parse errors of the days are transparent: they are reported with their day already, and
+/// printing the chain of sources would repeat their message otherwise.
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("Could not read the input for day {day:02}: {source}")]
+    ReadInput { day: u8, source: io::Error },
+    #[error(transparent)]
+    Day01(#[from] crate::day01::ParseInputError),
+    #[error(transparent)]
+    Day02(#[from] crate::day02::ParseInputError),
+    #[error(transparent)]
+    Day03(#[from] crate::day03::ParseInputError),
+    #[error(transparent)]
+    Day04(#[from] crate::day04::ParseInputError),
+    #[error(transparent)]
+    Day05(#[from] crate::day05::ParseError),
+    #[error(transparent)]
+    Day06(#[from] crate::day06::ParseInputError),
+    #[error(transparent)]
+    Day07(#[from] crate::day07::ParseInputError),
+    #[error(transparent)]
+    Day08(#[from] crate::day08::ParseInputError),
+    #[error(transparent)]
+    Day09(#[from] crate::day09::ParseInputError),
+    #[error(transparent)]
+    Day10(#[from] crate::day10::ParseInputError),
+    #[error(transparent)]
+    Day11(#[from] crate::day11::ParseInputError),
+    #[error(transparent)]
+    Day12(#[from] crate::day12::ParseInputError),
+    #[error(transparent)]
+    Day13(#[from] crate::day13::ParseInputError),
+    #[error(transparent)]
+    Day14(#[from] crate::day14::ParseInputError),
+    #[error(transparent)]
+    Day15(#[from] crate::day15::ParseInputError),
+    #[error(transparent)]
+    Day16(#[from] crate::day16::ParseInputError),
+    #[error(transparent)]
+    Day17(#[from] crate::day17::ParseInputError),
+    #[error(transparent)]
+    Day18(#[from] crate::day18::ParseInputError),
+    #[error(transparent)]
+    Day19(#[from] crate::day19::ParseInputError),
+    #[error(transparent)]
+    Day20(#[from] crate::day20::ParseInputError),
+    #[error(transparent)]
+    Day21(#[from] crate::day21::ParseInputError),
+    #[error(transparent)]
+    Day22(#[from] crate::day22::ParseInputError),
+    #[error(transparent)]
+    Day23(#[from] crate::day23::ParseInputError),
+    #[error(transparent)]
+    Day24(#[from] crate::day24::ParseInputError),
+    #[error(transparent)]
+    Day25(#[from] crate::day25::ParseInputError),
+}
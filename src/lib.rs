@@ -5,38 +5,73 @@
 #![feature(iterator_try_collect)]
 #![feature(slice_split_once)]
 #![feature(byte_slice_trim_ascii)]
-
 #![warn(clippy::pedantic)]
 #![allow(clippy::missing_panics_doc)]
 
-use std::time::SystemTime;
+use std::time::Instant;
+
+pub use error::Error;
+use report::Reporter;
+use runner::{RunOptions, Summary};
+use solution::DynSolution;
 
 macro_rules! days {
     ($($val:literal => $mod: ident),* $(,)?) => {
         $(
             pub mod $mod;
         )*
-        pub fn run(day: Option<usize>, repeat: Option<usize>) {
-            let start = SystemTime::now();
-            $(
-                #[allow(clippy::zero_prefixed_literal)]
-                if let None | Some($val) = day {
-                    if let Some(repeat) = repeat {
-                        for _ in 0..repeat {
-                            $mod::profile();
-                        }
-                    } else {
-                        $mod::run();
-                    }
-                }
-            )*
-            let d = SystemTime::now().duration_since(start).unwrap();
-            println!("Duration: {}:{:02}:{:02}.{:06}", d.as_secs()/3600, d.as_secs()/60%60, d.as_secs()%60, d.subsec_micros());
-        }
+
+        /// All implemented days, in order
+        pub static DAYS: &[&dyn DynSolution] = &[$(&$mod::Puzzle),*];
     };
 }
 
-mod aoclib;
+pub mod answers;
+pub mod aoclib;
+pub mod cli;
+pub mod diagnostic;
+mod error;
+pub mod report;
+pub mod runner;
+pub mod scaffold;
+pub mod solution;
+
+/// Run the selected days, on the embedded inputs or on inputs read at runtime.
+///
+/// # Errors
+///
+/// Returns an error if an input could not be read from the source. Inputs that could not be
+/// parsed are reported with the results of their day, and counted in the [`Summary`].
+pub fn run(options: &RunOptions) -> Result<Summary, Error> {
+    let start = Instant::now();
+    let mut summary = Summary::default();
+    let days: Vec<_> = options
+        .days
+        .iter()
+        .filter_map(|&day| get_day(day))
+        .map(|solution| {
+            let day = solution.day();
+            let text = options
+                .source
+                .read(day)
+                .map_err(|source| Error::ReadInput { day, source })?;
+            Ok((solution, text))
+        })
+        .collect::<Result<_, Error>>()?;
+    let mut reporter = Reporter::new(options.format);
+    runner::run_days(&days, options, |result| {
+        summary.add(&result);
+        reporter.day(&result);
+    });
+    reporter.finish(start.elapsed(), &summary);
+    Ok(summary)
+}
+
+/// Look up the solution for a day in [`DAYS`]
+#[must_use]
+pub fn get_day(day: u8) -> Option<&'static dyn DynSolution> {
+    DAYS.iter().copied().find(|solution| solution.day() == day)
+}
 
 days! {
     01 => day01,
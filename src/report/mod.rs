@@ -0,0 +1,374 @@
+use std::fmt::Write;
+use std::time::Duration;
+
+use crate::answers::Verdict;
+use crate::runner::{DayResult, Stats, Summary};
+
+/// How the runner prints its results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Tree of days, inputs and parts, for reading in a terminal
+    #[default]
+    Text,
+    /// Array of JSON objects, one per day, input and part
+    Json,
+    /// CSV with a header row, one row per day, input and part
+    Csv,
+}
+
+/// Prints the results of each day as they arrive, in the selected format
+#[derive(Debug)]
+pub struct Reporter {
+    format: OutputFormat,
+    records: usize,
+}
+
+impl Reporter {
+    /// Create a reporter, and print the header of the format
+    #[must_use]
+    pub fn new(format: OutputFormat) -> Self {
+        match format {
+            OutputFormat::Text => {}
+            OutputFormat::Json => println!("["),
+            OutputFormat::Csv => println!("{}", FIELDS.join(",")),
+        }
+        Self { format, records: 0 }
+    }
+
+    pub fn day(&mut self, result: &DayResult) {
+        match self.format {
+            OutputFormat::Text => print_text(result),
+            OutputFormat::Json => {
+                for record in records(result) {
+                    if self.records > 0 {
+                        println!(",");
+                    }
+                    print!("  {}", record.to_json());
+                    self.records += 1;
+                }
+            }
+            OutputFormat::Csv => {
+                for record in records(result) {
+                    println!("{}", record.to_csv());
+                }
+            }
+        }
+    }
+
+    /// Print the footer of the format. The text format ends with the total time and answer count.
+    pub fn finish(self, duration: Duration, summary: &Summary) {
+        match self.format {
+            OutputFormat::Text => {
+                let d = duration;
+                println!(
+                    "Duration: {}:{:02}:{:02}.{:06}",
+                    d.as_secs() / 3600,
+                    d.as_secs() / 60 % 60,
+                    d.as_secs() % 60,
+                    d.subsec_micros()
+                );
+                if summary.passed + summary.failed > 0 {
+                    print!(
+                        "Answers: {} passed, {} failed",
+                        summary.passed, summary.failed
+                    );
+                    if summary.unchecked > 0 {
+                        print!(", {} unchecked", summary.unchecked);
+                    }
+                    println!();
+                } else if summary.unchecked > 0 {
+                    println!("Answers: {} unchecked", summary.unchecked);
+                }
+                if summary.invalid > 0 {
+                    println!("Invalid inputs: {}", summary.invalid);
+                }
+            }
+            OutputFormat::Json if self.records > 0 => println!("\n]"),
+            OutputFormat::Json => println!("]"),
+            OutputFormat::Csv => {}
+        }
+    }
+}
+
+fn print_text(result: &DayResult) {
+    println!(".Day {:02}", result.day);
+    if let Some(err) = &result.sheet_error {
+        println!("|'-{err}");
+    }
+    for input in &result.inputs {
+        let mut title = format!("{}{}", input.name[..1].to_uppercase(), &input.name[1..]);
+        if input.runs() > 1 {
+            write!(title, " ({} runs)", input.runs()).unwrap();
+        }
+        println!("++{title}");
+        if let Some(err) = &input.error {
+            match &input.location {
+                Some(location) => {
+                    println!("|'-{err}, at {location}");
+                    for line in location.caret().lines() {
+                        println!("|   {line}");
+                    }
+                }
+                None => println!("|'-{err}"),
+            }
+            continue;
+        }
+        let mut lines = vec![format!("Parse: {}", format_times(&input.parse))];
+        if !input.transform.is_empty() {
+            lines.push(format!("Transform: {}", format_times(&input.transform)));
+        }
+        for part in &input.parts {
+            let verdict = match &part.verdict {
+                Verdict::Pass => " (PASS)".to_string(),
+                Verdict::Fail { expected } => format!(" (FAIL, expected {expected})"),
+                Verdict::Unchecked => " (unchecked)".to_string(),
+            };
+            lines.push(format!(
+                "Part {}: {}{verdict} [{}]",
+                part.part,
+                part.answer,
+                format_times(&part.times)
+            ));
+        }
+        for (i, line) in lines.iter().enumerate() {
+            let branch = if i + 1 == lines.len() { '\'' } else { '+' };
+            println!("|{branch}-{line}");
+        }
+    }
+    println!("')");
+}
+
+/// A single time, or the statistics of repeated runs
+fn format_times(samples: &[Duration]) -> String {
+    match (samples, Stats::from_samples(samples)) {
+        ([time], _) => format!("{time:.2?}"),
+        (_, Some(stats)) => stats.to_string(),
+        (_, None) => "-".to_string(),
+    }
+}
+
+const FIELDS: [&str; 13] = [
+    "day",
+    "input",
+    "part",
+    "answer",
+    "expected",
+    "status",
+    "error",
+    "line",
+    "column",
+    "runs",
+    "parse_ns",
+    "transform_ns",
+    "part_ns",
+];
+
+/// One row of the machine readable formats. Times are the median of all runs.
+#[derive(Debug, Default)]
+struct Record<'a> {
+    day: u8,
+    input: Option<&'a str>,
+    part: Option<u8>,
+    answer: Option<&'a str>,
+    expected: Option<&'a str>,
+    status: &'static str,
+    error: Option<&'a str>,
+    line: Option<usize>,
+    column: Option<usize>,
+    runs: usize,
+    parse: Option<Duration>,
+    transform: Option<Duration>,
+    time: Option<Duration>,
+}
+
+enum Value<'a> {
+    Null,
+    Int(u128),
+    Str(&'a str),
+}
+
+fn records(result: &DayResult) -> Vec<Record<'_>> {
+    let median = |samples: &[Duration]| Stats::from_samples(samples).map(|stats| stats.median);
+    let mut records = Vec::new();
+    if let Some(err) = &result.sheet_error {
+        records.push(Record {
+            day: result.day,
+            status: "error",
+            error: Some(err),
+            ..Record::default()
+        });
+    }
+    for input in &result.inputs {
+        let base = Record {
+            day: result.day,
+            input: Some(&input.name),
+            runs: input.runs(),
+            parse: median(&input.parse),
+            transform: median(&input.transform),
+            ..Record::default()
+        };
+        if let Some(err) = &input.error {
+            records.push(Record {
+                status: "error",
+                error: Some(err),
+                line: input.location.as_ref().map(|location| location.line),
+                column: input.location.as_ref().map(|location| location.column),
+                ..base
+            });
+            continue;
+        }
+        for part in &input.parts {
+            records.push(Record {
+                part: Some(part.part),
+                answer: part.answer.as_str(),
+                expected: part.expected.as_deref(),
+                status: match part.verdict {
+                    Verdict::Pass => "pass",
+                    Verdict::Fail { .. } => "fail",
+                    Verdict::Unchecked => "unchecked",
+                },
+                time: median(&part.times),
+                ..base
+            });
+        }
+    }
+    records
+}
+
+impl<'a> From<Option<&'a str>> for Value<'a> {
+    fn from(value: Option<&'a str>) -> Self {
+        value.map_or(Value::Null, Value::Str)
+    }
+}
+
+impl Record<'_> {
+    fn values(&self) -> [Value<'_>; FIELDS.len()] {
+        let nanos = |time: Option<Duration>| time.map_or(Value::Null, |t| Value::Int(t.as_nanos()));
+        [
+            Value::Int(self.day.into()),
+            Value::from(self.input),
+            self.part
+                .map_or(Value::Null, |part| Value::Int(part.into())),
+            Value::from(self.answer),
+            Value::from(self.expected),
+            Value::Str(self.status),
+            Value::from(self.error),
+            self.line
+                .map_or(Value::Null, |line| Value::Int(line as u128)),
+            self.column
+                .map_or(Value::Null, |column| Value::Int(column as u128)),
+            Value::Int(self.runs as u128),
+            nanos(self.parse),
+            nanos(self.transform),
+            nanos(self.time),
+        ]
+    }
+
+    fn to_json(&self) -> String {
+        let mut json = String::from("{");
+        for (i, (name, value)) in FIELDS.iter().zip(self.values()).enumerate() {
+            if i > 0 {
+                json.push_str(", ");
+            }
+            write!(json, "\"{name}\": ").unwrap();
+            match value {
+                Value::Null => json.push_str("null"),
+                Value::Int(n) => write!(json, "{n}").unwrap(),
+                Value::Str(s) => {
+                    json.push('"');
+                    for ch in s.chars() {
+                        match ch {
+                            '"' => json.push_str("\\\""),
+                            '\\' => json.push_str("\\\\"),
+                            '\n' => json.push_str("\\n"),
+                            '\r' => json.push_str("\\r"),
+                            '\t' => json.push_str("\\t"),
+                            ch if ch.is_control() => write!(json, "\\u{:04x}", ch as u32).unwrap(),
+                            ch => json.push(ch),
+                        }
+                    }
+                    json.push('"');
+                }
+            }
+        }
+        json.push('}');
+        json
+    }
+
+    fn to_csv(&self) -> String {
+        let fields: Vec<String> = self
+            .values()
+            .into_iter()
+            .map(|value| match value {
+                Value::Null => String::new(),
+                Value::Int(n) => n.to_string(),
+                Value::Str(s) if s.contains([',', '"', '\n', '\r']) => {
+                    format!("\"{}\"", s.replace('"', "\"\""))
+                }
+                Value::Str(s) => s.to_string(),
+            })
+            .collect();
+        fields.join(",")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_formats() {
+        let record = Record {
+            day: 3,
+            input: Some("input"),
+            part: Some(1),
+            answer: Some("1,2"),
+            status: "pass",
+            runs: 1,
+            time: Some(Duration::from_nanos(1500)),
+            ..Record::default()
+        };
+        assert_eq!(
+            record.to_json(),
+            "{\"day\": 3, \"input\": \"input\", \"part\": 1, \"answer\": \"1,2\", \
+             \"expected\": null, \"status\": \"pass\", \"error\": null, \"line\": null, \
+             \"column\": null, \"runs\": 1, \"parse_ns\": null, \"transform_ns\": null, \
+             \"part_ns\": 1500}"
+        );
+        assert_eq!(record.to_csv(), "3,input,1,\"1,2\",,pass,,,,1,,,1500");
+    }
+
+    #[test]
+    fn json_escapes_quotes_and_control_characters() {
+        let record = Record {
+            error: Some("say \"hi\"\\\n\r\t\u{1}\u{7f}é"),
+            ..Record::default()
+        };
+        let json = record.to_json();
+        assert!(
+            json.contains(r#""error": "say \"hi\"\\\n\r\t\u0001\u007fé","#),
+            "{json}"
+        );
+    }
+
+    #[test]
+    fn csv_quotes_fields_with_separators() {
+        let csv = |error| {
+            let record = Record {
+                error: Some(error),
+                ..Record::default()
+            };
+            record.to_csv().split(',').nth(6).map(str::to_string)
+        };
+        assert_eq!(csv("plain text").unwrap(), "plain text");
+        assert_eq!(csv("say \"hi\"").unwrap(), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv("line\nbreak").unwrap(), "\"line\nbreak\"");
+        assert_eq!(csv("cr\rlf").unwrap(), "\"cr\rlf\"");
+        let record = Record {
+            answer: Some("a,b"),
+            expected: Some("\"x\",y"),
+            ..Record::default()
+        };
+        assert_eq!(record.to_csv(), "0,,,\"a,b\",\"\"\"x\"\",y\",,,,,0,,,");
+    }
+}
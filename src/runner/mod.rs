@@ -0,0 +1,546 @@
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::hint::black_box;
+use std::io::{self, Read};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::answers::{AnswerSheet, Verdict};
+use crate::diagnostic::Location;
+use crate::report::OutputFormat;
+use crate::solution::{Answer, DynSolution, Parts};
+
+/// Where to read the puzzle input from
+#[derive(Debug, Clone, Default)]
+pub enum InputSource {
+    /// The input compiled into the binary
+    #[default]
+    Embedded,
+    /// A single input file, or a directory containing `dayNN.txt` files
+    Path(PathBuf),
+    /// Standard input
+    Stdin,
+}
+
+impl InputSource {
+    /// Returns `true` if the source can only hold the input for a single day
+    #[must_use]
+    pub fn is_single(&self) -> bool {
+        match self {
+            Self::Embedded => false,
+            Self::Path(path) => !path.is_dir(),
+            Self::Stdin => true,
+        }
+    }
+
+    /// Read the input for `day`. Returns `None` for the embedded input.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file or standard input could not be read.
+    pub fn read(&self, day: u8) -> io::Result<Option<String>> {
+        match self {
+            Self::Embedded => Ok(None),
+            Self::Path(path) if path.is_dir() => {
+                std::fs::read_to_string(path.join(format!("day{day:02}.txt"))).map(Some)
+            }
+            Self::Path(path) => std::fs::read_to_string(path).map(Some),
+            Self::Stdin => {
+                let mut text = String::new();
+                io::stdin().read_to_string(&mut text)?;
+                Ok(Some(text))
+            }
+        }
+    }
+}
+
+/// Which of the embedded inputs to solve
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputFilter {
+    #[default]
+    All,
+    /// Only the examples from the puzzle text
+    Examples,
+    /// Only the real puzzle input
+    Input,
+}
+
+impl InputFilter {
+    /// Returns `true` if the embedded input named `name` is selected
+    #[must_use]
+    pub fn matches(self, name: &str) -> bool {
+        match self {
+            Self::All => true,
+            Self::Examples => name != "input",
+            Self::Input => name == "input",
+        }
+    }
+}
+
+/// Everything needed to run a selection of days
+#[derive(Debug, Clone)]
+pub struct RunOptions {
+    /// Days to run, in order
+    pub days: Vec<u8>,
+    pub parts: Parts,
+    /// Which embedded inputs to solve. Ignored for inputs read at runtime.
+    pub inputs: InputFilter,
+    pub source: InputSource,
+    /// Solve each input this many times, for profiling
+    pub repeat: Option<usize>,
+    pub format: OutputFormat,
+    /// Number of days to solve in parallel. Sequential by default, to not disturb the timings.
+    pub jobs: usize,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            days: crate::DAYS.iter().map(|solution| solution.day()).collect(),
+            parts: Parts::BOTH,
+            inputs: InputFilter::All,
+            source: InputSource::Embedded,
+            repeat: None,
+            format: OutputFormat::Text,
+            jobs: 1,
+        }
+    }
+}
+
+/// Number of answers that matched, or did not match, the answer sheets
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    /// Answers without an expected answer to check against
+    pub unchecked: usize,
+    /// Inputs that could not be parsed
+    pub invalid: usize,
+}
+
+impl Summary {
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && self.invalid == 0
+    }
+
+    /// Count the answers of a day, and the inputs that could not be parsed
+    pub fn add(&mut self, result: &DayResult) {
+        self.failed += usize::from(result.sheet_error.is_some());
+        for input in &result.inputs {
+            self.invalid += usize::from(input.error.is_some());
+            for part in &input.parts {
+                match part.verdict {
+                    Verdict::Pass => self.passed += 1,
+                    Verdict::Fail { .. } => self.failed += 1,
+                    Verdict::Unchecked => self.unchecked += 1,
+                }
+            }
+        }
+    }
+}
+
+/// Results of running one day
+#[derive(Debug, Clone)]
+pub struct DayResult {
+    pub day: u8,
+    /// Set if the answer sheet of the day could not be parsed
+    pub sheet_error: Option<String>,
+    pub inputs: Vec<InputResult>,
+}
+
+/// Results of solving one input, once or repeatedly
+#[derive(Debug, Clone)]
+pub struct InputResult {
+    pub name: String,
+    /// Set if the input could not be parsed, with the message of the [`crate::Error`]
+    pub error: Option<String>,
+    /// Where in the input the parse error happened, if known
+    pub location: Option<Location>,
+    /// Time spent parsing, one sample per run
+    pub parse: Vec<Duration>,
+    /// Time spent in the transform step, for the days that have one
+    pub transform: Vec<Duration>,
+    pub parts: Vec<PartResult>,
+}
+
+impl InputResult {
+    /// Number of times the input was solved
+    #[must_use]
+    pub fn runs(&self) -> usize {
+        self.parse.len()
+    }
+}
+
+/// Answer to one part of an input, checked against the answer sheet
+#[derive(Debug, Clone)]
+pub struct PartResult {
+    pub part: u8,
+    pub answer: Answer,
+    pub expected: Option<String>,
+    pub verdict: Verdict,
+    /// Time spent solving the part, one sample per run
+    pub times: Vec<Duration>,
+}
+
+/// Solve a day, on its embedded inputs or on `text` if the input was read at runtime.
+///
+/// The answers to the embedded inputs are checked against the answer sheet of the day. Inputs with
+/// recorded answers are only solved for the parts that have one, and the answers of all other
+/// inputs are unchecked.
+#[must_use]
+pub fn run_day(solution: &dyn DynSolution, text: Option<&str>, options: &RunOptions) -> DayResult {
+    let mut sheet = AnswerSheet::default();
+    let mut sheet_error = None;
+    let inputs: Vec<(&str, &str)> = if let Some(text) = text {
+        vec![("input", text)]
+    } else {
+        let inputs = solution.inputs();
+        match solution.answers().parse::<AnswerSheet>() {
+            Ok(parsed) => {
+                let unknown: Vec<_> = parsed
+                    .inputs()
+                    .filter(|&section| inputs.iter().all(|&(name, _)| name != section))
+                    .collect();
+                if !unknown.is_empty() {
+                    sheet_error = Some(format!(
+                        "Invalid answers.toml: No embedded input for [{}]",
+                        unknown.join("], [")
+                    ));
+                }
+                sheet = parsed;
+            }
+            Err(err) => sheet_error = Some(format!("Invalid answers.toml: {err}")),
+        }
+        inputs
+            .into_iter()
+            .filter(|&(name, _)| options.inputs.matches(name))
+            .collect()
+    };
+    let mut results = Vec::new();
+    for (name, text) in inputs {
+        let parts = if sheet.has_answers(name) {
+            Parts {
+                part_1: options.parts.part_1 && sheet.get(name, 1).is_some(),
+                part_2: options.parts.part_2 && sheet.get(name, 2).is_some(),
+            }
+        } else {
+            options.parts
+        };
+        if parts.part_1 || parts.part_2 {
+            let repeat = options.repeat.unwrap_or(1);
+            results.push(solve_input(solution, name, text, parts, repeat, &sheet));
+        }
+    }
+    DayResult {
+        day: solution.day(),
+        sheet_error,
+        inputs: results,
+    }
+}
+
+/// Solve each day with its input, and pass the results to `report` in the same order.
+///
+/// With more than one job, the days are spread over a pool of threads. Results that finish
+/// early are held back until all days before them have been reported. A panicking solution does
+/// not stop the other days: their results are all reported, and the panic is passed on after
+/// that.
+pub fn run_days(
+    days: &[(&dyn DynSolution, Option<String>)],
+    options: &RunOptions,
+    mut report: impl FnMut(DayResult),
+) {
+    if options.jobs <= 1 {
+        for (solution, text) in days {
+            report(run_day(*solution, text.as_deref(), options));
+        }
+        return;
+    }
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..options.jobs.min(days.len()) {
+            let sender = sender.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some((solution, text)) = days.get(index) else {
+                    break;
+                };
+                let result = run_day(*solution, text.as_deref(), options);
+                if sender.send((index, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+        let mut pending = BTreeMap::new();
+        let mut reported = 0;
+        for (index, result) in receiver {
+            pending.insert(index, result);
+            while let Some(result) = pending.remove(&reported) {
+                report(result);
+                reported += 1;
+            }
+        }
+        // A day whose solution panicked is never sent, so the days after it are still held back.
+        // Report them before the panic is passed on at the end of the scope.
+        for result in pending.into_values() {
+            report(result);
+        }
+    });
+}
+
+fn solve_input(
+    solution: &dyn DynSolution,
+    name: &str,
+    text: &str,
+    parts: Parts,
+    repeat: usize,
+    sheet: &AnswerSheet,
+) -> InputResult {
+    let mut result = InputResult {
+        name: name.to_string(),
+        error: None,
+        location: None,
+        parse: Vec::with_capacity(repeat),
+        transform: Vec::new(),
+        parts: Vec::new(),
+    };
+    let mut answers = None;
+    let mut times = [Vec::new(), Vec::new()];
+    for _ in 0..repeat {
+        match black_box(solution.solve(name, black_box(text), parts)) {
+            Ok(run) => {
+                let timings = run.timings;
+                result.parse.push(timings.parse);
+                result.transform.extend(timings.transform);
+                times[0].extend(timings.part_1);
+                times[1].extend(timings.part_2);
+                answers.get_or_insert(run);
+            }
+            Err(err) => {
+                result.error = Some(err.to_string());
+                result.location = Location::of_error(&err, text);
+                return result;
+            }
+        }
+    }
+    let Some(answers) = answers else {
+        return result;
+    };
+    let [times_1, times_2] = times;
+    for (part, answer, times) in [(1, answers.part_1, times_1), (2, answers.part_2, times_2)] {
+        let Some(answer) = answer else {
+            continue;
+        };
+        let expected = sheet.get(name, part);
+        result.parts.push(PartResult {
+            part,
+            verdict: Verdict::check(answer.as_str(), expected),
+            expected: expected.map(str::to_string),
+            answer,
+            times,
+        });
+    }
+    result
+}
+
+/// Summary statistics of repeated time measurements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    /// Returns `None` if there are no samples.
+    #[must_use]
+    pub fn from_samples(samples: &[Duration]) -> Option<Self> {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let count = u32::try_from(sorted.len()).ok().filter(|&n| n > 0)?;
+        let mean = sorted.iter().sum::<Duration>() / count;
+        let variance = sorted
+            .iter()
+            .map(|&sample| (sample.as_secs_f64() - mean.as_secs_f64()).powi(2))
+            .sum::<f64>()
+            / f64::from(count);
+        let mid = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[mid - 1] + sorted[mid]) / 2
+        } else {
+            sorted[mid]
+        };
+        Some(Self {
+            min: sorted[0],
+            median,
+            mean,
+            stddev: Duration::from_secs_f64(variance.sqrt()),
+        })
+    }
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "min {:.2?}, median {:.2?}, mean {:.2?}, stddev {:.2?}",
+            self.min, self.median, self.mean, self.stddev
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::*;
+    use crate::error::Error;
+    use crate::solution::Answers;
+
+    /// A day that answers its own number, slower for the earlier days
+    struct Fake {
+        day: u8,
+        panics: bool,
+        answers: &'static str,
+    }
+
+    impl DynSolution for Fake {
+        fn day(&self) -> u8 {
+            self.day
+        }
+
+        fn inputs(&self) -> Vec<(&'static str, &'static str)> {
+            vec![("input", "")]
+        }
+
+        fn answers(&self) -> &'static str {
+            self.answers
+        }
+
+        fn solve(&self, _name: &str, _text: &str, _parts: Parts) -> Result<Answers, Error> {
+            thread::sleep(Duration::from_millis(u64::from(10 - self.day) * 2));
+            assert!(!self.panics, "day {} panicked", self.day);
+            Ok(Answers {
+                part_1: Some(Answer::Found(self.day.to_string())),
+                ..Answers::default()
+            })
+        }
+    }
+
+    fn run_fakes(fakes: &[Fake], jobs: usize) -> (Vec<u8>, bool) {
+        let days: Vec<_> = fakes
+            .iter()
+            .map(|fake| (fake as &dyn DynSolution, Some(String::new())))
+            .collect();
+        let options = RunOptions {
+            jobs,
+            ..RunOptions::default()
+        };
+        let mut reported = Vec::new();
+        let run = panic::catch_unwind(AssertUnwindSafe(|| {
+            run_days(&days, &options, |result| {
+                let answer = &result.inputs[0].parts[0].answer;
+                assert_eq!(answer.as_str(), Some(result.day.to_string().as_str()));
+                reported.push(result.day);
+            });
+        }));
+        (reported, run.is_err())
+    }
+
+    fn fakes(panicking: Option<u8>) -> Vec<Fake> {
+        (1..=9)
+            .map(|day| Fake {
+                day,
+                panics: Some(day) == panicking,
+                answers: "",
+            })
+            .collect()
+    }
+
+    #[test]
+    fn answer_sections_must_match_an_input() {
+        let run = |answers| {
+            let fake = Fake {
+                day: 9,
+                panics: false,
+                answers,
+            };
+            run_day(&fake, None, &RunOptions::default())
+        };
+        let result = run("[input]\npart_1 = 9\n");
+        assert_eq!(result.sheet_error, None);
+        assert_eq!(result.inputs[0].parts[0].verdict, Verdict::Pass);
+        let result = run("[input]\npart_1 = 9\n[inptu]\npart_2 = 1\n[example]\n");
+        assert_eq!(
+            result.sheet_error.as_deref(),
+            Some("Invalid answers.toml: No embedded input for [inptu], [example]")
+        );
+        assert_eq!(result.inputs[0].parts[0].verdict, Verdict::Pass);
+        let mut summary = Summary::default();
+        summary.add(&result);
+        assert!(!summary.is_success());
+    }
+
+    #[test]
+    fn days_are_reported_in_order() {
+        for jobs in [1, 3, 16] {
+            assert_eq!(run_fakes(&fakes(None), jobs), ((1..=9).collect(), false));
+        }
+    }
+
+    #[test]
+    fn days_after_a_panic_are_still_reported() {
+        let (reported, panicked) = run_fakes(&fakes(Some(2)), 3);
+        assert!(panicked);
+        assert_eq!(reported, [1, 3, 4, 5, 6, 7, 8, 9]);
+    }
+
+    fn millis(samples: &[u64]) -> Vec<Duration> {
+        samples.iter().copied().map(Duration::from_millis).collect()
+    }
+
+    #[test]
+    fn stats_of_no_and_one_sample() {
+        assert_eq!(Stats::from_samples(&[]), None);
+        let sample = Duration::from_micros(1234);
+        let stats = Stats::from_samples(&[sample]).unwrap();
+        assert_eq!(
+            stats,
+            Stats {
+                min: sample,
+                median: sample,
+                mean: sample,
+                stddev: Duration::ZERO,
+            }
+        );
+    }
+
+    #[test]
+    fn stats_median_of_even_and_odd_counts() {
+        let stats = Stats::from_samples(&millis(&[4, 1, 3, 2])).unwrap();
+        assert_eq!(stats.min, Duration::from_millis(1));
+        assert_eq!(stats.median, Duration::from_micros(2500));
+        let stats = Stats::from_samples(&millis(&[9, 1, 5])).unwrap();
+        assert_eq!(stats.median, Duration::from_millis(5));
+        assert_eq!(stats.mean, Duration::from_millis(5));
+    }
+
+    #[test]
+    fn stats_population_stddev() {
+        let stats = Stats::from_samples(&millis(&[2, 4, 4, 4, 5, 5, 7, 9])).unwrap();
+        assert_eq!(stats.mean, Duration::from_millis(5));
+        assert!(
+            stats.stddev.abs_diff(Duration::from_millis(2)) < Duration::from_nanos(10),
+            "{stats}"
+        );
+        let same = Stats::from_samples(&millis(&[3, 3, 3])).unwrap();
+        assert_eq!(same.stddev, Duration::ZERO);
+    }
+}
@@ -0,0 +1,310 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use thiserror::Error;
+
+const TEMPLATE_DIR: &str = "src/dayXX";
+const TEMPLATE_FILES: [&str; 4] = ["mod.rs", "answers.toml", "example.txt", "input.txt"];
+
+#[derive(Debug, Error)]
+pub enum ScaffoldError {
+    #[error("Day {0:02} already exists: {1}")]
+    DayExists(u8, PathBuf),
+    #[error("Template not found: {0}. Run from the root of the repository.")]
+    MissingTemplate(PathBuf),
+    #[error("Could not find {1} in {0}")]
+    UnexpectedLayout(PathBuf, &'static str),
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
+/// Create a new day from the `dayXX` template, in the repository at `root`.
+///
+/// Creates `src/dayNN` and `benches/dayNN.rs`, and registers the day in `days!` in `lib.rs`, in
+/// the crate level `Error`, and as a `[[bench]]` in `Cargo.toml`. Nothing is written if the day
+/// already exists in any of these places.
+///
+/// Returns the created and updated files.
+///
+/// # Errors
+///
+/// Returns an error if the day already exists, if a file is missing or does not look as
+/// expected, or if a file could not be read or written.
+pub fn new_day(root: &Path, day: u8) -> Result<Vec<PathBuf>, ScaffoldError> {
+    let name = format!("day{day:02}");
+    let template_dir = root.join(TEMPLATE_DIR);
+    if !template_dir.is_dir() {
+        return Err(ScaffoldError::MissingTemplate(template_dir));
+    }
+    let day_dir = root.join("src").join(&name);
+    let bench_path = root.join("benches").join(format!("{name}.rs"));
+    for path in [&day_dir, &bench_path] {
+        if path.exists() {
+            return Err(ScaffoldError::DayExists(day, path.clone()));
+        }
+    }
+
+    // Prepare all edits before writing anything
+    let lib_path = root.join("src/lib.rs");
+    let lib = add_to_days_macro(&lib_path, &fs::read_to_string(&lib_path)?, day)?;
+    let error_path = root.join("src/error/mod.rs");
+    let error = add_error_variant(&error_path, &fs::read_to_string(&error_path)?, day)?;
+    let cargo_path = root.join("Cargo.toml");
+    let cargo = add_bench_entry(&cargo_path, &fs::read_to_string(&cargo_path)?, day)?;
+    let mut templates = Vec::new();
+    for file in TEMPLATE_FILES {
+        let mut text = fs::read_to_string(template_dir.join(file))?;
+        if file == "mod.rs" {
+            text = text.replace("const DAY: u8 = XX;", &format!("const DAY: u8 = {day};"));
+        }
+        templates.push((day_dir.join(file), text));
+    }
+
+    fs::create_dir(&day_dir)?;
+    let mut written = Vec::new();
+    for (path, text) in templates {
+        fs::write(&path, text)?;
+        written.push(path);
+    }
+    fs::write(&bench_path, bench(&name))?;
+    written.push(bench_path);
+    for (path, text) in [(lib_path, lib), (error_path, error), (cargo_path, cargo)] {
+        fs::write(&path, text)?;
+        written.push(path);
+    }
+    Ok(written)
+}
+
+/// Insert `NN => dayNN` into the list of the `days!` invocation, keeping it sorted
+fn add_to_days_macro(path: &Path, text: &str, day: u8) -> Result<String, ScaffoldError> {
+    let layout_error = || ScaffoldError::UnexpectedLayout(path.to_path_buf(), "days! { ... }");
+    let start = text.find("\ndays! {\n").ok_or_else(layout_error)? + "\ndays! {\n".len();
+    let len = text[start..].find("\n}").ok_or_else(layout_error)?;
+    let mut days: Vec<u8> = Vec::new();
+    for line in text[start..start + len].lines() {
+        let (number, _) = line.trim().split_once(" => ").ok_or_else(layout_error)?;
+        days.push(number.parse().map_err(|_| layout_error())?);
+    }
+    if days.contains(&day) {
+        return Err(ScaffoldError::DayExists(day, path.to_path_buf()));
+    }
+    days.push(day);
+    days.sort_unstable();
+    let list: Vec<_> = days
+        .iter()
+        .map(|day| format!("    {day:02} => day{day:02}"))
+        .collect();
+    Ok(format!(
+        "{}{}{}",
+        &text[..start],
+        list.join(",\n"),
+        &text[start + len..]
+    ))
+}
+
+/// Insert a variant for the parse error of the day into the crate level `Error`, keeping the days
+/// in order
+fn add_error_variant(path: &Path, text: &str, day: u8) -> Result<String, ScaffoldError> {
+    if text.contains(&format!("    Day{day:02}(")) {
+        return Err(ScaffoldError::DayExists(day, path.to_path_buf()));
+    }
+    let variant = format!(
+        "    #[error(transparent)]\n    Day{day:02}(#[from] crate::day{day:02}::ParseInputError),\n"
+    );
+    let layout_error = || ScaffoldError::UnexpectedLayout(path.to_path_buf(), "enum Error");
+    let start = text.find("pub enum Error {\n").ok_or_else(layout_error)?;
+    let end = start + text[start..].find("\n}\n").ok_or_else(layout_error)? + 1;
+    let insert_at = match (day + 1..=25)
+        .find_map(|next| text[start..end].find(&format!("\n    Day{next:02}(")))
+    {
+        // Start of the line with the `#[error]` attribute of the next day
+        Some(offset) => {
+            start
+                + text[start..start + offset]
+                    .rfind('\n')
+                    .map_or(0, |ix| ix + 1)
+        }
+        None => end,
+    };
+    Ok(format!(
+        "{}{variant}{}",
+        &text[..insert_at],
+        &text[insert_at..]
+    ))
+}
+
+/// Insert a `[[bench]]` entry for the day into `Cargo.toml`, before the entries of later days
+fn add_bench_entry(path: &Path, text: &str, day: u8) -> Result<String, ScaffoldError> {
+    let entry = |day: u8| format!("[[bench]]\nname = \"day{day:02}\"\n");
+    if text.contains(&entry(day)) {
+        return Err(ScaffoldError::DayExists(day, path.to_path_buf()));
+    }
+    let entry = format!("{}harness = false\n", entry(day));
+    match (day + 1..=25)
+        .find_map(|next| text.find(&format!("[[bench]]\nname = \"day{next:02}\"\n")))
+    {
+        Some(offset) => Ok(format!("{}{entry}\n{}", &text[..offset], &text[offset..])),
+        None => Ok(format!("{}\n\n{entry}", text.trim_end())),
+    }
+}
+
+fn bench(name: &str) -> String {
+    format!(
+        "use divan::AllocProfiler;
+
+#[global_allocator]
+static GLOBAL_ALLOC: AllocProfiler = AllocProfiler::system();
+
+fn main() {{
+    divan::main();
+}}
+
+mod tests {{
+    use aoc_rust_2023::{name}::*;
+    use divan::Bencher;
+
+    #[divan::bench]
+    fn run_parse_input(b: Bencher) {{
+        b.bench(parse_test_input);
+    }}
+
+    #[divan::bench]
+    fn run_part_1(b: Bencher) {{
+        let input = parse_test_input();
+        b.bench(|| part_1(&input));
+    }}
+
+    #[divan::bench]
+    fn run_part_2(b: Bencher) {{
+        let input = parse_test_input();
+        b.bench(|| part_2(&input));
+    }}
+}}
+"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIB: &str = "mod day01;\n\ndays! {\n    01 => day01,\n    05 => day05\n}\n";
+    const ERROR: &str = "pub enum Error {
+    #[error(\"Could not read the input for day {day:02}: {source}\")]
+    ReadInput { day: u8, source: io::Error },
+    #[error(transparent)]
+    Day01(#[from] crate::day01::ParseInputError),
+    #[error(transparent)]
+    Day05(#[from] crate::day05::ParseInputError),
+}
+";
+    const CARGO: &str =
+        "[package]\nname = \"aoc\"\n\n[[bench]]\nname = \"day05\"\nharness = false\n";
+
+    /// An empty directory for the test `name`, removed first if it is left from an earlier run
+    fn temp_root(name: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("scaffold-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        root
+    }
+
+    #[test]
+    fn days_macro_stays_sorted() {
+        let path = Path::new("lib.rs");
+        let lib = add_to_days_macro(path, LIB, 3).unwrap();
+        assert!(lib.ends_with("days! {\n    01 => day01,\n    03 => day03,\n    05 => day05\n}\n"));
+        let lib = add_to_days_macro(path, LIB, 25).unwrap();
+        assert!(lib.ends_with("    05 => day05,\n    25 => day25\n}\n"));
+        assert!(matches!(
+            add_to_days_macro(path, LIB, 5),
+            Err(ScaffoldError::DayExists(5, _))
+        ));
+        assert!(matches!(
+            add_to_days_macro(path, "mod day01;\n", 3),
+            Err(ScaffoldError::UnexpectedLayout(_, _))
+        ));
+    }
+
+    #[test]
+    fn error_variants_stay_in_order() {
+        let path = Path::new("error.rs");
+        let error = add_error_variant(path, ERROR, 3).unwrap();
+        let day03 = error.find("    #[error(transparent)]\n    Day03(");
+        let day05 = error.find("    Day05(");
+        assert!(day03.is_some() && day03 < day05, "{error}");
+        assert!(error.contains("Day01(#[from] crate::day01::ParseInputError),\n    #[error"));
+        let error = add_error_variant(path, ERROR, 7).unwrap();
+        assert!(error.ends_with("Day07(#[from] crate::day07::ParseInputError),\n}\n"));
+        assert!(matches!(
+            add_error_variant(path, ERROR, 1),
+            Err(ScaffoldError::DayExists(1, _))
+        ));
+    }
+
+    #[test]
+    fn bench_entries_stay_in_order() {
+        let path = Path::new("Cargo.toml");
+        let cargo = add_bench_entry(path, CARGO, 3).unwrap();
+        assert!(cargo.contains(
+            "[[bench]]\nname = \"day03\"\nharness = false\n\n[[bench]]\nname = \"day05\"\n"
+        ));
+        let cargo = add_bench_entry(path, CARGO, 9).unwrap();
+        assert!(
+            cargo.ends_with("harness = false\n\n[[bench]]\nname = \"day09\"\nharness = false\n")
+        );
+        assert!(matches!(
+            add_bench_entry(path, CARGO, 5),
+            Err(ScaffoldError::DayExists(5, _))
+        ));
+    }
+
+    #[test]
+    fn new_day_writes_all_files() {
+        let root = temp_root("new_day");
+        fs::create_dir_all(root.join(TEMPLATE_DIR)).unwrap();
+        fs::create_dir_all(root.join("src/error")).unwrap();
+        fs::create_dir_all(root.join("benches")).unwrap();
+        for file in TEMPLATE_FILES {
+            fs::write(root.join(TEMPLATE_DIR).join(file), "const DAY: u8 = XX;\n").unwrap();
+        }
+        fs::write(root.join("src/lib.rs"), LIB).unwrap();
+        fs::write(root.join("src/error/mod.rs"), ERROR).unwrap();
+        fs::write(root.join("Cargo.toml"), CARGO).unwrap();
+
+        let written = new_day(&root, 3).unwrap();
+        assert_eq!(written.len(), TEMPLATE_FILES.len() + 4);
+        let module = fs::read_to_string(root.join("src/day03/mod.rs")).unwrap();
+        assert_eq!(module, "const DAY: u8 = 3;\n");
+        let bench = fs::read_to_string(root.join("benches/day03.rs")).unwrap();
+        assert!(bench.contains("use aoc_rust_2023::day03::*;"));
+        assert!(fs::read_to_string(root.join("src/lib.rs"))
+            .unwrap()
+            .contains("03 => day03"));
+
+        // Nothing is changed when the day exists
+        let cargo = fs::read_to_string(root.join("Cargo.toml")).unwrap();
+        assert!(matches!(
+            new_day(&root, 3),
+            Err(ScaffoldError::DayExists(3, _))
+        ));
+        assert!(matches!(
+            new_day(&root, 5),
+            Err(ScaffoldError::DayExists(5, _))
+        ));
+        assert_eq!(fs::read_to_string(root.join("Cargo.toml")).unwrap(), cargo);
+        assert!(!root.join("src/day05").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn new_day_needs_the_template() {
+        let root = temp_root("no_template");
+        assert!(matches!(
+            new_day(&root, 3),
+            Err(ScaffoldError::MissingTemplate(_))
+        ));
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
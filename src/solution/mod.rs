@@ -0,0 +1,193 @@
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use crate::Error;
+
+/// Common interface of the solutions for each day.
+///
+/// The puzzle text is first parsed into `Input`, then optionally transformed into the value the
+/// parts operate on. Days without a transform step use the same type for both, and return the
+/// input unchanged from [`Solution::transform`].
+pub trait Solution {
+    /// Day of the puzzle, 1 to 25
+    const DAY: u8;
+
+    /// Embedded puzzle inputs, as `(name, text, params)`. The examples come first, and the real
+    /// input is named `"input"`. An example can be listed under several names, to check it with
+    /// different parameters.
+    const INPUTS: &'static [(&'static str, &'static str, Self::Params)];
+    /// Contents of `answers.toml`, with the expected answers for [`Solution::INPUTS`]
+    const ANSWERS: &'static str;
+    /// `true` if [`Solution::transform`] does real work, and should be timed separately
+    const TRANSFORMS: bool = false;
+    /// `false` for days without a second part, like the last one
+    const HAS_PART_2: bool = true;
+
+    /// Result of parsing the puzzle text
+    type Input<'a>;
+    /// Value passed to the parts, after the transform step
+    type Transformed<'a>;
+    /// Error returned if the puzzle text could not be parsed. Wrapped in the crate level [`Error`].
+    type ParseError<'a>: StdError + Into<Error> + 'a;
+    type Answer1: Display;
+    type Answer2: Display;
+    /// Values that differ between the examples and the real input, like a number of steps. `()`
+    /// for most days. Inputs read at runtime use the parameters of `"input"`.
+    type Params: 'static;
+
+    /// Parse the puzzle text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text is not valid input for this day.
+    fn parse(text: &str) -> Result<Self::Input<'_>, Self::ParseError<'_>>;
+
+    /// Prepare the parsed input for the parts.
+    fn transform(input: Self::Input<'_>) -> Self::Transformed<'_>;
+
+    /// Returns `None` if the input has no answer.
+    fn part_1(input: &Self::Transformed<'_>, params: &Self::Params) -> Option<Self::Answer1>;
+
+    /// Returns `None` if the input has no answer. Not called if [`Solution::HAS_PART_2`] is
+    /// `false`.
+    fn part_2(input: &Self::Transformed<'_>, params: &Self::Params) -> Option<Self::Answer2>;
+}
+
+/// Selection of parts to solve
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parts {
+    pub part_1: bool,
+    pub part_2: bool,
+}
+
+impl Parts {
+    pub const BOTH: Self = Self {
+        part_1: true,
+        part_2: true,
+    };
+}
+
+/// Answer of one solved part, formatted for display
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Found(String),
+    /// The part ran, but the input has no answer
+    NotFound,
+}
+
+impl Answer {
+    #[must_use]
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::Found(answer) => Some(answer),
+            Self::NotFound => None,
+        }
+    }
+}
+
+impl<T: Display> From<Option<T>> for Answer {
+    fn from(answer: Option<T>) -> Self {
+        answer.map_or(Self::NotFound, |answer| Self::Found(answer.to_string()))
+    }
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Found(answer) => f.write_str(answer),
+            Self::NotFound => f.write_str("no answer"),
+        }
+    }
+}
+
+/// Answers of the solved parts. Parts that were not run are `None`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    pub part_1: Option<Answer>,
+    pub part_2: Option<Answer>,
+    pub timings: Timings,
+}
+
+/// Time spent in each phase of solving an input. Phases that were not run are `None`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Timings {
+    pub parse: Duration,
+    pub transform: Option<Duration>,
+    pub part_1: Option<Duration>,
+    pub part_2: Option<Duration>,
+}
+
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+/// Object safe version of [`Solution`], used to keep all days in one registry.
+pub trait DynSolution: Sync {
+    fn day(&self) -> u8;
+
+    /// Names and texts of the embedded inputs
+    fn inputs(&self) -> Vec<(&'static str, &'static str)>;
+
+    fn answers(&self) -> &'static str;
+
+    /// Parse, transform and solve the selected parts of the puzzle text, with the parameters of the
+    /// embedded input `name`. Unknown names get the parameters of the real input.
+    ///
+    /// # Errors
+    ///
+    /// Returns the parse error of the day if the text is not valid input for it.
+    fn solve(&self, name: &str, text: &str, parts: Parts) -> Result<Answers, Error>;
+}
+
+impl<S> DynSolution for S
+where
+    S: Solution + Sync,
+{
+    fn day(&self) -> u8 {
+        S::DAY
+    }
+
+    fn inputs(&self) -> Vec<(&'static str, &'static str)> {
+        S::INPUTS
+            .iter()
+            .map(|&(name, text, _)| (name, text))
+            .collect()
+    }
+
+    fn answers(&self) -> &'static str {
+        S::ANSWERS
+    }
+
+    fn solve(&self, name: &str, text: &str, parts: Parts) -> Result<Answers, Error> {
+        let params = [name, "input"]
+            .iter()
+            .find_map(|&name| S::INPUTS.iter().find(|input| input.0 == name))
+            .map(|(_, _, params)| params)
+            .expect("Each day has an input named \"input\"");
+        let (input, parse) = timed(|| S::parse(text));
+        let input = input.map_err(Into::into)?;
+        let (input, transform) = timed(|| S::transform(input));
+        let mut answers = Answers {
+            timings: Timings {
+                parse,
+                transform: S::TRANSFORMS.then_some(transform),
+                ..Timings::default()
+            },
+            ..Answers::default()
+        };
+        if parts.part_1 {
+            let (answer, time) = timed(|| S::part_1(&input, params));
+            answers.part_1 = Some(answer.into());
+            answers.timings.part_1 = Some(time);
+        }
+        if parts.part_2 && S::HAS_PART_2 {
+            let (answer, time) = timed(|| S::part_2(&input, params));
+            answers.part_2 = Some(answer.into());
+            answers.timings.part_2 = Some(time);
+        }
+        Ok(answers)
+    }
+}
@@ -0,0 +1,74 @@
+//! Every day must reject empty and malformed input with an error, rather than panicking or looping
+//! forever. Input that parses but has no answer must not panic or loop forever either.
+
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use aoc_rust_2023::solution::Parts;
+use aoc_rust_2023::DAYS;
+
+const INVALID_INPUTS: &[&str] = &["", "\n", "x", "garbage\n", "1 2 3\n???\n", "#.#\n..\n"];
+
+/// Malformed input for one day, as `(day, text)`
+const INVALID_DAY_INPUTS: &[(u8, &str)] = &[
+    (6, "Time:\nDistance:\n"),
+    (6, "Time: 7 15\nDistance: 9\n"),
+    (6, "Time: 7\nDistance: 9\nTime: 7\n"),
+    (6, "Time: -7\nDistance: 9\n"),
+    (8, "L\n\nAAA = (BBB, BBB)\n"),
+    (8, "L\n\nAAA = (AAA, AAA)\nAAA = (AAA, AAA)\n"),
+    (10, "S-7\n|.|\nL-.\n"),
+    (10, "S-7\n|.|\n|.|\n"),
+    (23, "###\n###\n###\n"),
+    (23, "#.#\n#.#\n###\n"),
+];
+
+/// Valid input for one day that has no answer for some part, as `(day, text)`
+const UNSOLVABLE_INPUTS: &[(u8, &str)] = &[
+    (6, "Time: 7\nDistance: 123456\n"),
+    (6, "Time: 99999999999 99999999999\nDistance: 9 9\n"),
+    (8, "L\n\nAAA = (AAA, AAA)\nZZZ = (ZZZ, ZZZ)\n"),
+    (8, "L\n\nBBB = (BBB, BBB)\n"),
+    (23, "#.#\n###\n#.#\n"),
+    (25, "a: b c d e\nb: c d e\nc: d e\nd: e\n"),
+];
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Solve `text` as input for `day` on another thread. Returns `true` if it was rejected, and fails
+/// the test if solving panicked or did not finish in time.
+fn is_rejected(day: u8, text: &'static str) -> bool {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let solution = DAYS.iter().find(|s| s.day() == day).expect("Day exists");
+        let is_err = solution.solve("input", text, Parts::BOTH).is_err();
+        // The receiver is gone if the test already timed out.
+        let _ = tx.send(is_err);
+    });
+    match rx.recv_timeout(TIMEOUT) {
+        Ok(is_err) => is_err,
+        Err(mpsc::RecvTimeoutError::Timeout) => panic!("Day {day} hung on {text:?}"),
+        Err(mpsc::RecvTimeoutError::Disconnected) => panic!("Day {day} panicked on {text:?}"),
+    }
+}
+
+#[test]
+fn invalid_input_is_rejected() {
+    for solution in DAYS {
+        let day = solution.day();
+        for &text in INVALID_INPUTS {
+            assert!(is_rejected(day, text), "Day {day} accepted {text:?}");
+        }
+    }
+    for &(day, text) in INVALID_DAY_INPUTS {
+        assert!(is_rejected(day, text), "Day {day} accepted {text:?}");
+    }
+}
+
+#[test]
+fn unsolvable_input_is_accepted() {
+    for &(day, text) in UNSOLVABLE_INPUTS {
+        assert!(!is_rejected(day, text), "Day {day} rejected {text:?}");
+    }
+}